@@ -0,0 +1,68 @@
+use std::error::Error;
+
+use crate::framebuffer::Framebuffer;
+
+pub struct Image {
+    pub pixels: Vec<u32>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Image {
+    pub fn load(path: &str, width: usize, height: usize) -> Result<Image, Box<dyn Error>> {
+        let (pixels, width, height) = load_and_resize_image(path, width, height)?;
+        Ok(Image { pixels, width, height })
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, x_offset: usize, y_offset: usize) {
+        render_image(framebuffer, &self.pixels, self.width, self.height, x_offset, y_offset);
+    }
+}
+
+// Images shared by several scenes, loaded once at startup
+pub struct Assets {
+    pub start_screen: Image,
+    pub fail_screen: Image,
+}
+
+impl Assets {
+    pub fn load() -> Result<Assets, Box<dyn Error>> {
+        Ok(Assets {
+            start_screen: Image::load("image/alice_start.jpeg", 600, 600)?,
+            fail_screen: Image::load("image/alice_fail.jpeg", 600, 600)?,
+        })
+    }
+}
+
+pub fn load_and_resize_image(path: &str, new_width: usize, new_height: usize) -> Result<(Vec<u32>, usize, usize), Box<dyn Error>> {
+    let img = image::open(path)?;
+    let img = img.resize(new_width as u32, new_height as u32, image::imageops::FilterType::Nearest);
+    let img = img.to_rgba8();
+    let pixels = img
+        .pixels()
+        .map(|p| {
+            let rgba = p.0;
+            ((rgba[0] as u32) << 16) | ((rgba[1] as u32) << 8) | (rgba[2] as u32) | ((rgba[3] as u32) << 24)
+        })
+        .collect();
+    Ok((pixels, new_width, new_height))
+}
+
+pub fn render_image(framebuffer: &mut Framebuffer, image: &[u32], image_width: usize, image_height: usize, x_offset: usize, y_offset: usize) {
+    let framebuffer_width = framebuffer.width;
+    let framebuffer_height = framebuffer.height;
+
+    for y in 0..image_height {
+        for x in 0..image_width {
+            let pixel_index = y * image_width + x;
+            if pixel_index < image.len() {
+                let pixel = image[pixel_index];
+                let dest_x = x_offset + x;
+                let dest_y = y_offset + y;
+                if dest_x < framebuffer_width && dest_y < framebuffer_height {
+                    framebuffer.point(dest_x, dest_y, pixel);
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,19 @@
-use crate::{font::draw_char, framebuffer::Framebuffer};
+use crate::{
+    font::{draw_text, ellipsize, text_height, text_width, LINE_ADVANCE, LINE_SPACING},
+    framebuffer::Framebuffer,
+};
+
+const DEFAULT_PADDING: usize = 4;
 
 pub struct Button {
     pub x: usize,
     pub y: usize,
     pub width: usize,
     pub height: usize,
-    pub text: String,
+    pub text: String, // May contain '\n' to split the label into several lines
+    pub padding: usize,
     pub is_selected: bool,
+    pub is_hovered: bool,
 }
 
 impl Button {
@@ -17,32 +24,48 @@ impl Button {
             width,
             height,
             text: text.to_string(),
+            padding: DEFAULT_PADDING,
             is_selected: false,
+            is_hovered: false,
         }
     }
 
     pub fn draw(&self, framebuffer: &mut Framebuffer) {
-        let color = if self.is_selected { 0xFFFFFFFF } else { 0xFF808080 }; // White if selected, gray otherwise
+        let color = if self.is_selected {
+            0xFFFFFFFF // White if selected
+        } else if self.is_hovered {
+            0xFFB0B0B0 // Light gray under the mouse
+        } else {
+            0xFF808080 // Gray otherwise
+        };
         let text_color = if self.is_selected { 0xFF000000 } else { 0xFFFFFFFF }; // Black text if selected, white otherwise
 
         // Draw button background
         for x in self.x..self.x + self.width {
             for y in self.y..self.y + self.height {
-                if x < framebuffer.width && y < framebuffer.height {
-                    framebuffer.point(x, y, color);
-                }
+                framebuffer.point(x, y, color);
             }
         }
 
-        // Draw text in the center of the button
-        let text_x = self.x + (self.width - (self.text.len() * 6)) / 2; // Center text horizontally
-        let text_y = self.y + (self.height - 7) / 2; // Center text vertically
+        // Area left for the label once the padding is removed
+        let inner_width = self.width.saturating_sub(2 * self.padding);
+        let inner_height = self.height.saturating_sub(2 * self.padding);
+
+        // Only keep the lines that fit vertically, clipping the rest
+        let max_lines = (inner_height + LINE_SPACING) / LINE_ADVANCE;
+        let lines: Vec<String> = self
+            .text
+            .lines()
+            .take(max_lines)
+            .map(|line| ellipsize(line, inner_width))
+            .collect();
+
+        // Center the block of text inside the padded area
+        let text_y = self.y + self.padding + inner_height.saturating_sub(text_height(lines.len())) / 2;
 
-        for (i, ch) in self.text.chars().enumerate() {
-            draw_char(framebuffer, text_x + i * 6, text_y, ch, text_color);
+        for (i, line) in lines.iter().enumerate() {
+            let text_x = self.x + self.padding + inner_width.saturating_sub(text_width(line)) / 2;
+            draw_text(framebuffer, text_x, text_y + i * LINE_ADVANCE, line, text_color);
         }
     }
 }
-
-
-
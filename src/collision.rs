@@ -0,0 +1,66 @@
+// Collision between circles (the player, enemies) and the maze grid, where every
+// character of the maze file is one cell
+
+pub const PLAYER_RADIUS: f32 = 0.2;
+
+pub fn is_wall_char(cell: char) -> bool {
+    matches!(cell, '+' | '-' | '|')
+}
+
+// Cells outside the maze count as walls so nothing can leave it
+pub fn is_wall_cell(maze: &[Vec<char>], cell_x: i32, cell_y: i32) -> bool {
+    if cell_x < 0 || cell_y < 0 {
+        return true;
+    }
+    match maze.get(cell_y as usize).and_then(|row| row.get(cell_x as usize)) {
+        Some(&cell) => is_wall_char(cell),
+        None => true,
+    }
+}
+
+// Whether a circle centred at (x, y) overlaps any wall cell
+pub fn circle_hits_wall(maze: &[Vec<char>], x: f32, y: f32, radius: f32) -> bool {
+    let min_x = (x - radius).floor() as i32;
+    let max_x = (x + radius).floor() as i32;
+    let min_y = (y - radius).floor() as i32;
+    let max_y = (y + radius).floor() as i32;
+
+    for cell_y in min_y..=max_y {
+        for cell_x in min_x..=max_x {
+            if !is_wall_cell(maze, cell_x, cell_y) {
+                continue;
+            }
+
+            // Distance from the centre to the closest point of the cell
+            let closest_x = x.clamp(cell_x as f32, cell_x as f32 + 1.0);
+            let closest_y = y.clamp(cell_y as f32, cell_y as f32 + 1.0);
+            let dx = x - closest_x;
+            let dy = y - closest_y;
+            if dx * dx + dy * dy < radius * radius {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Moves a circle by (dx, dy), resolving each axis on its own so that a blocked
+// axis does not stop movement along the other one (sliding along walls).
+// Returns the new position.
+pub fn move_and_slide(maze: &[Vec<char>], x: f32, y: f32, dx: f32, dy: f32, radius: f32) -> (f32, f32) {
+    // Split long moves so fast movers cannot tunnel through thin walls
+    let max_step = (radius * 0.5).max(0.01);
+    let steps = (dx.abs().max(dy.abs()) / max_step).ceil().max(1.0) as usize;
+    let (step_x, step_y) = (dx / steps as f32, dy / steps as f32);
+
+    let (mut x, mut y) = (x, y);
+    for _ in 0..steps {
+        if !circle_hits_wall(maze, x + step_x, y, radius) {
+            x += step_x;
+        }
+        if !circle_hits_wall(maze, x, y + step_y, radius) {
+            y += step_y;
+        }
+    }
+    (x, y)
+}
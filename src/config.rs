@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+
+// One `key = value` line of a config file, with its 1-based line number for errors
+pub struct ConfigLine {
+    pub line_no: usize,
+    pub key: String,
+    pub value: String,
+}
+
+// Reads the `key = value` lines of a config file, skipping blank lines and `#`
+// comments. A missing file is not an error and gives `None`.
+pub fn read_config(path: &str) -> Result<Option<Vec<ConfigLine>>, Box<dyn Error>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    parse_config(path, &contents).map(Some)
+}
+
+// Parses `key = value` lines that were read from `path`
+pub fn parse_config(path: &str, contents: &str) -> Result<Vec<ConfigLine>, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{}:{}: expected 'key = value'", path, index + 1))?;
+        lines.push(ConfigLine {
+            line_no: index + 1,
+            key: key.trim().to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+    Ok(lines)
+}
+
+// Comma separated numbers such as `2.5, 3.5`, as used for positions
+pub fn parse_numbers(value: &str) -> Option<Vec<f32>> {
+    value.split(',').map(|part| part.trim().parse().ok()).collect()
+}
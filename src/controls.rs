@@ -0,0 +1,199 @@
+use minifb::Key;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use crate::config::{read_config, ConfigLine};
+
+pub const CONTROLS_FILE: &str = "controls.cfg";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    Sprint,
+    TurnLeft,
+    TurnRight,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Use,     // Picks the highlighted menu entry
+    Confirm, // Leaves the success and fail screens
+    Pause,
+    ToggleMouse, // Releases or recaptures the mouse while playing
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::Sprint,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Use,
+        Action::Confirm,
+        Action::Pause,
+        Action::ToggleMouse,
+    ];
+
+    // Name used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBackward => "move_backward",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::Sprint => "sprint",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::MenuLeft => "menu_left",
+            Action::MenuRight => "menu_right",
+            Action::Use => "use",
+            Action::Confirm => "confirm",
+            Action::Pause => "pause",
+            Action::ToggleMouse => "toggle_mouse",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        self.name().replace('_', " ").to_uppercase()
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+}
+
+// Keys that can be bound to an action, named as in minifb
+pub const BINDABLE_KEYS: [Key; 56] = [
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+    Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+    Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+    Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::Up, Key::Down, Key::Left, Key::Right,
+    Key::Space, Key::Enter, Key::Escape, Key::Tab, Key::Backspace,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
+    Key::LeftAlt, Key::RightAlt, Key::Home, Key::End,
+    Key::PageUp, Key::PageDown, Key::Delete,
+];
+
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    BINDABLE_KEYS.iter().copied().find(|key| key_name(*key).eq_ignore_ascii_case(name))
+}
+
+pub struct Controls {
+    bindings: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        bindings.insert(Action::MoveForward, vec![Key::W, Key::Up]);
+        bindings.insert(Action::MoveBackward, vec![Key::S, Key::Down]);
+        bindings.insert(Action::StrafeLeft, vec![Key::Q]);
+        bindings.insert(Action::StrafeRight, vec![Key::E]);
+        bindings.insert(Action::Sprint, vec![Key::LeftShift, Key::RightShift]);
+        bindings.insert(Action::TurnLeft, vec![Key::A, Key::Left]);
+        bindings.insert(Action::TurnRight, vec![Key::D, Key::Right]);
+        bindings.insert(Action::MenuUp, vec![Key::Up]);
+        bindings.insert(Action::MenuDown, vec![Key::Down]);
+        bindings.insert(Action::MenuLeft, vec![Key::Left]);
+        bindings.insert(Action::MenuRight, vec![Key::Right]);
+        bindings.insert(Action::Use, vec![Key::P]);
+        bindings.insert(Action::Confirm, vec![Key::Enter]);
+        bindings.insert(Action::Pause, vec![Key::Escape]);
+        bindings.insert(Action::ToggleMouse, vec![Key::Tab]);
+        Controls { bindings }
+    }
+}
+
+impl Controls {
+    // Reads `action = Key, Key` lines; actions missing from the file keep their
+    // defaults. Problems are printed as warnings instead of stopping the game:
+    // a bad line keeps the defaults for its action, an unreadable file keeps
+    // them all.
+    pub fn load(path: &str) -> Controls {
+        let mut controls = Controls::default();
+        let lines = match read_config(path) {
+            Ok(Some(lines)) => lines,
+            Ok(None) => return controls,
+            Err(err) => {
+                eprintln!("warning: {}; using the default controls", err);
+                return controls;
+            }
+        };
+
+        for line in lines {
+            match parse_binding(path, &line) {
+                Ok((action, keys)) => {
+                    controls.bindings.insert(action, keys);
+                }
+                Err(err) => eprintln!("warning: {}; line ignored", err),
+            }
+        }
+        controls
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut contents = String::from("# action = key, key\n");
+        for action in Action::ALL {
+            let keys: Vec<String> = self.keys(action).iter().map(|key| key_name(*key)).collect();
+            contents.push_str(&format!("{} = {}\n", action.name(), keys.join(", ")));
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Replaces every key bound to the action with `key`
+    pub fn bind(&mut self, action: Action, key: Key) {
+        self.bindings.insert(action, vec![key]);
+    }
+}
+
+// One `action = Key, Key` line of the controls file
+fn parse_binding(path: &str, line: &ConfigLine) -> Result<(Action, Vec<Key>), String> {
+    let action = Action::from_name(&line.key)
+        .ok_or_else(|| format!("{}:{}: unknown action '{}'", path, line.line_no, line.key))?;
+
+    let mut bound = Vec::new();
+    for key in line.value.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+        let key = key_from_name(key)
+            .ok_or_else(|| format!("{}:{}: unknown key '{}'", path, line.line_no, key))?;
+        bound.push(key);
+    }
+    Ok((action, bound))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The rebind screen only offers these keys, so each must load back from the file
+    #[test]
+    fn bindable_keys_read_back_by_name() {
+        for key in BINDABLE_KEYS {
+            assert_eq!(key_from_name(&key_name(key)), Some(key));
+        }
+        assert_eq!(key_from_name("F1"), None);
+    }
+}
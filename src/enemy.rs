@@ -0,0 +1,184 @@
+use std::f32::consts::PI;
+
+use crate::collision::move_and_slide;
+use crate::pathfinding::{Cell, PathCache};
+use crate::perception::{can_hear, can_see, AlertState, Perception};
+use crate::player::Player;
+
+pub const CAT_RADIUS: f32 = 0.2;
+pub const DEFAULT_CAT_SPEED: f32 = 1.2; // Cells per second, well under the player's walk
+
+const CHASE_SPEED_MULTIPLIER: f32 = 1.5;
+const TURN_SPEED: f32 = 3.0; // Radians per second when turning to look at something
+const LOOK_AROUND_SPEED: f32 = 1.5; // Radians per second while searching
+const WAYPOINT_REACHED_DISTANCE: f32 = 0.05;
+const STUCK_DISTANCE: f32 = 0.001; // Moving less than this in a tick counts as blocked
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EnemyKind {
+    Cat,      // Stays on its patrol and only watches the player
+    Cheshire, // Leaves its patrol to chase and search for the player
+}
+
+// A cat that walks back and forth along its patrol waypoints. A cat without
+// waypoints stays where it is until something makes it move.
+#[derive(Clone)]
+pub struct Enemy {
+    pub kind: EnemyKind,
+    pub position: na::Point3<f32>,
+    pub facing: f32, // Angle the cat looks towards, like `Player::angle`
+    pub waypoints: Vec<na::Point3<f32>>, // Starts with the spawn position
+    pub speed: f32,
+    pub perception: Perception,
+    next_waypoint: usize,
+    forward: bool, // Whether the patrol is walking towards the last waypoint
+    path: PathCache,
+    previous_position: na::Point3<f32>, // Position before the last tick
+}
+
+impl Enemy {
+    pub fn new(kind: EnemyKind, position: na::Point3<f32>, mut waypoints: Vec<na::Point3<f32>>, speed: f32) -> Self {
+        // Start out looking along the patrol
+        let facing = waypoints.first().map_or(0.0, |first| (first.y - position.y).atan2(first.x - position.x));
+        waypoints.insert(0, position);
+        Enemy {
+            kind,
+            position,
+            facing,
+            waypoints,
+            speed,
+            perception: Perception::default(),
+            next_waypoint: 1,
+            forward: true,
+            path: PathCache::default(),
+            previous_position: position,
+        }
+    }
+
+    pub fn has_spotted_player(&self) -> bool {
+        self.perception.state == AlertState::Chase
+    }
+
+    // Puts the cat somewhere else, as when loading a saved game. It heads back
+    // to its patrol from there.
+    pub fn place(&mut self, position: na::Point3<f32>, facing: f32) {
+        self.position = position;
+        self.previous_position = position;
+        self.facing = facing;
+        self.perception = Perception::default();
+        self.perception.state = AlertState::Return;
+        self.path.clear();
+    }
+
+    pub fn forget_player(&mut self) {
+        self.perception.forget_player();
+        self.path.clear();
+    }
+
+    // Senses the player, then acts on the alert state for one tick. Moving
+    // slides along walls like the player.
+    pub fn update(&mut self, maze: &[Vec<char>], player: &Player, dt: f32) {
+        self.previous_position = self.position;
+
+        let (x, y) = (self.position.x, self.position.y);
+        let distance = ((player.x - x).powi(2) + (player.y - y).powi(2)).sqrt();
+        let sees = can_see(maze, x, y, self.facing, player);
+        self.perception.update(sees, can_hear(x, y, player), distance, player, dt);
+
+        let moves = self.kind == EnemyKind::Cheshire;
+        let last_known = self.perception.last_known.map(|point| na::Point3::new(point.x, point.y, self.position.z));
+        match (self.perception.state, last_known) {
+            (AlertState::Idle, _) => self.patrol(maze, dt),
+            (AlertState::Chase, Some(target)) if moves => {
+                let step = self.speed * CHASE_SPEED_MULTIPLIER * dt;
+                let target_cell = (target.x as usize, target.y as usize);
+                if sees && self.cell() == target_cell {
+                    self.move_towards(maze, target, step); // Sharing a cell, so go straight for the player
+                } else {
+                    self.follow_path(maze, target_cell, step);
+                }
+            }
+            (AlertState::Search, Some(target)) => {
+                // Go to where the player was last noticed, then look around
+                let target_cell = (target.x as usize, target.y as usize);
+                if !(moves && self.follow_path(maze, target_cell, self.speed * dt)) {
+                    self.facing += LOOK_AROUND_SPEED * dt;
+                }
+            }
+            (AlertState::Return, _) => {
+                let waypoint = self.waypoints[self.next_waypoint.min(self.waypoints.len() - 1)];
+                let target_cell = (waypoint.x as usize, waypoint.y as usize);
+                if !(moves && self.follow_path(maze, target_cell, self.speed * dt)) {
+                    self.path.clear();
+                    self.perception.back_on_patrol();
+                }
+            }
+            // Stop and stare at what caught the cat's attention
+            (_, Some(target)) => self.turn_towards(target, dt),
+            (_, None) => {}
+        }
+    }
+
+    // Where the cat should be drawn `alpha` of the way between the last two ticks
+    pub fn interpolated_position(&self, alpha: f32) -> na::Point3<f32> {
+        self.previous_position + (self.position - self.previous_position) * alpha
+    }
+
+    fn cell(&self) -> Cell {
+        (self.position.x as usize, self.position.y as usize)
+    }
+
+    fn patrol(&mut self, maze: &[Vec<char>], dt: f32) {
+        let Some(target) = self.waypoints.get(self.next_waypoint).copied() else {
+            return;
+        };
+        if !self.move_towards(maze, target, self.speed * dt) {
+            self.advance_waypoint(); // Reached it, or a wall is in the way
+        }
+    }
+
+    // Walks along the grid path to the centre of `target`. Returns false once
+    // there, or if there is no way there.
+    fn follow_path(&mut self, maze: &[Vec<char>], target: Cell, step: f32) -> bool {
+        let from = self.cell();
+        let Some((x, y)) = self.path.next_cell(maze, from, target) else {
+            return false;
+        };
+        let centre = na::Point3::new(x as f32 + 0.5, y as f32 + 0.5, self.position.z);
+        self.move_towards(maze, centre, step) || from != target
+    }
+
+    // Steps towards `target`, returning false if already there or blocked
+    fn move_towards(&mut self, maze: &[Vec<char>], target: na::Point3<f32>, step: f32) -> bool {
+        let offset = target - self.position;
+        let distance = offset.magnitude();
+        if distance <= WAYPOINT_REACHED_DISTANCE {
+            return false;
+        }
+
+        let step = offset * step.min(distance) / distance;
+        self.facing = step.y.atan2(step.x);
+        let (x, y) = move_and_slide(maze, self.position.x, self.position.y, step.x, step.y, CAT_RADIUS);
+        let moved = ((x - self.position.x).powi(2) + (y - self.position.y).powi(2)).sqrt();
+        self.position.x = x;
+        self.position.y = y;
+        moved >= STUCK_DISTANCE
+    }
+
+    fn turn_towards(&mut self, target: na::Point3<f32>, dt: f32) {
+        let wanted = (target.y - self.position.y).atan2(target.x - self.position.x);
+        let difference = (wanted - self.facing + PI).rem_euclid(2.0 * PI) - PI;
+        self.facing += difference.clamp(-TURN_SPEED * dt, TURN_SPEED * dt);
+    }
+
+    // Turns around at either end of the patrol
+    fn advance_waypoint(&mut self) {
+        let last = self.waypoints.len() - 1;
+        if self.forward && self.next_waypoint == last {
+            self.forward = false;
+        } else if !self.forward && self.next_waypoint == 0 {
+            self.forward = true;
+        }
+        self.next_waypoint = if self.forward { self.next_waypoint + 1 } else { self.next_waypoint - 1 };
+    }
+}
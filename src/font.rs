@@ -1,37 +1,131 @@
 use crate::framebuffer::Framebuffer;
 
-pub const FONT: [u8; 10] = [
-    // 'A' (5x5 bitmap)
-    0b00100, // Row 1
-    0b01010, // Row 2
-    0b10001, // Row 3
-    0b11111, // Row 4
-    0b10001, // Row 5
-    // 'B' (5x5 bitmap)
-    0b11110, // Row 1
-    0b10001, // Row 2
-    0b11110, // Row 3
-    0b10001, // Row 4
-    0b11110, // Row 5
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 5;
+pub const GLYPH_SPACING: usize = 1; // Empty columns between two glyphs
+pub const LINE_SPACING: usize = 2; // Empty rows between two lines of text
+
+pub const GLYPH_ADVANCE: usize = GLYPH_WIDTH + GLYPH_SPACING;
+pub const LINE_ADVANCE: usize = GLYPH_HEIGHT + LINE_SPACING;
+
+const ELLIPSIS: &str = "...";
+
+// Each glyph is a 5x5 bitmap, one byte per row, most significant bit on the left
+const FONT: [(char, [u8; 5]); 60] = [
+    ('A', [0b00100, 0b01010, 0b10001, 0b11111, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b11110, 0b10001, 0b11110]),
+    ('C', [0b01111, 0b10000, 0b10000, 0b10000, 0b01111]),
+    ('D', [0b11110, 0b10001, 0b10001, 0b10001, 0b11110]),
+    ('E', [0b11111, 0b10000, 0b11110, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b11110, 0b10000, 0b10000]),
+    ('G', [0b01111, 0b10000, 0b10011, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b11111, 0b10001, 0b10001]),
+    ('I', [0b11111, 0b00100, 0b00100, 0b00100, 0b11111]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10010, 0b10100, 0b11000, 0b10100, 0b10010]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b11001, 0b10101, 0b10011, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b11110, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b11110, 0b10100, 0b10010]),
+    ('S', [0b01111, 0b10000, 0b01110, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10101, 0b11011, 0b10001]),
+    ('X', [0b10001, 0b01010, 0b00100, 0b01010, 0b10001]),
+    ('Y', [0b10001, 0b01010, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('0', [0b01110, 0b10011, 0b10101, 0b11001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b11110, 0b00001, 0b01110, 0b10000, 0b11111]),
+    ('3', [0b11110, 0b00001, 0b00110, 0b00001, 0b11110]),
+    ('4', [0b10010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b11110]),
+    ('6', [0b01110, 0b10000, 0b11110, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b00100]),
+    ('8', [0b01110, 0b10001, 0b01110, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b01111, 0b00001, 0b01110]),
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00100, 0b01000]),
+    (':', [0b00000, 0b00100, 0b00000, 0b00100, 0b00000]),
+    (';', [0b00000, 0b00100, 0b00000, 0b00100, 0b01000]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00000, 0b00100]),
+    ('?', [0b01110, 0b00001, 0b00110, 0b00000, 0b00100]),
+    ('-', [0b00000, 0b00000, 0b01110, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b01110, 0b00100, 0b00000]),
+    ('/', [0b00001, 0b00010, 0b00100, 0b01000, 0b10000]),
+    ('(', [0b00010, 0b00100, 0b00100, 0b00100, 0b00010]),
+    (')', [0b01000, 0b00100, 0b00100, 0b00100, 0b01000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01110]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b01110]),
+    ('\'', [0b00100, 0b00100, 0b00000, 0b00000, 0b00000]),
+    ('"', [0b01010, 0b01010, 0b00000, 0b00000, 0b00000]),
+    ('_', [0b00000, 0b00000, 0b00000, 0b00000, 0b11111]),
+    ('=', [0b00000, 0b11111, 0b00000, 0b11111, 0b00000]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b00100, 0b00010]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00100, 0b01000]),
+    ('%', [0b11001, 0b11010, 0b00100, 0b01011, 0b10011]),
+    ('#', [0b01010, 0b11111, 0b01010, 0b11111, 0b01010]),
+    ('*', [0b00000, 0b01010, 0b00100, 0b01010, 0b00000]),
+    ('|', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
 ];
 
+fn glyph(ch: char) -> Option<&'static [u8; 5]> {
+    let ch = ch.to_ascii_uppercase(); // Lowercase letters reuse the uppercase bitmaps
+    FONT.iter().find(|(c, _)| *c == ch).map(|(_, bitmap)| bitmap)
+}
+
 pub fn draw_char(framebuffer: &mut Framebuffer, x: usize, y: usize, ch: char, color: u32) {
-    let idx = match ch {
-        'A' => 0,
-        'B' => 5,
-        _ => return, // Ignore characters not in the FONT array
+    let bitmap = match glyph(ch).or_else(|| glyph('?')) {
+        Some(bitmap) => bitmap,
+        None => return,
     };
-    
-    for row in 0..5 { // Adjusted to 5 rows
-        let bitmap = FONT[idx + row];
-        for col in 0..5 {
-            if (bitmap >> (4 - col)) & 1 == 1 {
-                if x + col < framebuffer.width && y + row < framebuffer.height {
-                    framebuffer.point(x + col, y + row, color);
-                }
+
+    for (row, bits) in bitmap.iter().enumerate() {
+        for col in 0..GLYPH_WIDTH {
+            if (bits >> (GLYPH_WIDTH - 1 - col)) & 1 == 1 {
+                framebuffer.point(x + col, y + row, color);
             }
         }
     }
 }
 
+// Draws a single line of text, ignoring any line breaks
+pub fn draw_text(framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, color: u32) {
+    for (i, ch) in text.chars().filter(|&ch| ch != '\n').enumerate() {
+        draw_char(framebuffer, x + i * GLYPH_ADVANCE, y, ch, color);
+    }
+}
 
+// Width in pixels of a single line, without trailing spacing after the last glyph
+pub fn text_width(text: &str) -> usize {
+    let count = text.chars().count();
+    (count * GLYPH_ADVANCE).saturating_sub(GLYPH_SPACING)
+}
+
+// Height in pixels of a block of `lines` lines of text
+pub fn text_height(lines: usize) -> usize {
+    (lines * LINE_ADVANCE).saturating_sub(LINE_SPACING)
+}
+
+// Shortens `text` so it fits in `max_width` pixels, ending it with "..." when there
+// is room for it and clipping it otherwise
+pub fn ellipsize(text: &str, max_width: usize) -> String {
+    if text_width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let max_chars = (max_width + GLYPH_SPACING) / GLYPH_ADVANCE;
+    let ellipsis_chars = ELLIPSIS.chars().count();
+
+    if max_chars > ellipsis_chars {
+        let kept: String = text.chars().take(max_chars - ellipsis_chars).collect();
+        format!("{}{}", kept, ELLIPSIS)
+    } else {
+        text.chars().take(max_chars).collect()
+    }
+}
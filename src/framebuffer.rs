@@ -1,10 +1,6 @@
 use crate::raycaster::{cast_ray, Intersect};
-use std::fs::File;
-use std::io::{self, Write};
-use std::path::Path;
-use std::cmp::{max, min};
-use nalgebra_glm::Vec3;
 
+use crate::assets::Image;
 use crate::player::Player;
 
 
@@ -35,13 +31,22 @@ impl Framebuffer {
         }
     }
 
-    pub fn set_background_color(&mut self, color: u32) {
+    // Darkens everything drawn so far, e.g. behind an overlay menu
+    pub fn dim(&mut self) {
         for pixel in self.pixels.iter_mut() {
-            *pixel = color;
+            *pixel = (*pixel & 0xFF000000) | ((*pixel >> 1) & 0x007F7F7F); // Halve each color channel
+        }
+    }
+
+    // Blends every pixel halfway towards `color`
+    pub fn tint(&mut self, color: u32) {
+        let half = (color >> 1) & 0x007F7F7F;
+        for pixel in self.pixels.iter_mut() {
+            *pixel = (*pixel & 0xFF000000) | (((*pixel >> 1) & 0x007F7F7F) + half);
         }
     }
 
-    pub fn set_foreground_color(&mut self, color: u32) {
+    pub fn set_background_color(&mut self, color: u32) {
         for pixel in self.pixels.iter_mut() {
             *pixel = color;
         }
@@ -83,7 +88,7 @@ impl Framebuffer {
                         '+' => color,       // No additional effect, use texture color
                         '-' => color,       // Apply some effect or different color if needed
                         '|' => color,       // Apply some effect or different color if needed
-                        _ => 0xFF606c38,    // Default to white for unknown types
+                        _ => 0xFF606C38,    // Default to white for unknown types
                     };
 
                     self.point(ray, y, final_color);
@@ -96,16 +101,14 @@ impl Framebuffer {
 
 
 
-pub fn render_fov_with_2d(&mut self, maze: &[Vec<char>], player: &Player, cell_size: usize, cat_positions: &[na::Point3<f32>],wall_texture: &Vec<u32>, // Texture data
-    texture_width: usize,   // Texture width
-    texture_height: usize ) {
+pub fn render_fov_with_2d(&mut self, maze: &[Vec<char>], player: &Player, cell_size: usize, cat_positions: &[na::Point3<f32>], wall_texture: &Image, sky_color: u32) {
     // Render the 3D FOV
     self.clear();
-    self.set_background_color(0xFF8ecae6);
-    self.render_fov(maze, player, wall_texture, // Texture data
-    texture_width,   // Texture width
-    texture_height );
-    let ground_color = 0xFF606c38;
+    self.set_background_color(sky_color);
+    self.render_fov(maze, player, &wall_texture.pixels, // Texture data
+    wall_texture.width,   // Texture width
+    wall_texture.height );
+    let ground_color = 0xFF606C38;
     let ground_start = self.height / 2+27;
     for y in ground_start..self.height {
         for x in 0..self.width {
@@ -115,7 +118,6 @@ pub fn render_fov_with_2d(&mut self, maze: &[Vec<char>], player: &Player, cell_s
 
     // Define the size and position of the 2D map in the corner
     let map_width = maze[0].len() * cell_size;
-    let map_height = maze.len() * cell_size;
     let offset_x = self.width - map_width - 10; // 10px padding from the right
     let offset_y = 10; // 10px padding from the top
 
@@ -126,6 +128,7 @@ pub fn render_fov_with_2d(&mut self, maze: &[Vec<char>], player: &Player, cell_s
                 '+' | '-' | '|' => 0xFF000000, // Black for walls
                 'p' => 0xFF00FF00,              // Green for player
                 'g' => 0xFFFF0000,              // Red for goal
+                'k' => 0xFF00FFFF,              // Cyan for checkpoints
                 _ => 0xFF819349,                // White for empty space
             };
 
@@ -179,52 +182,4 @@ pub fn render_fov_with_2d(&mut self, maze: &[Vec<char>], player: &Player, cell_s
     }
 }
 
-
-    fn cast_ray(&self, player: &Player, maze: &[Vec<char>], ray_angle: f32) -> (f32, Option<char>) {
-        // Calculate the direction of the ray
-        let mut ray_x = player.x;
-        let mut ray_y = player.y;
-
-        // Normalize the angle
-        let mut angle = ray_angle;
-        if angle < 0.0 {
-            angle += 2.0 * std::f32::consts::PI;
-        } else if angle > 2.0 * std::f32::consts::PI {
-            angle -= 2.0 * std::f32::consts::PI;
-        }
-
-        // Determine the step direction for x and y
-        let step_x = angle.cos();
-        let step_y = angle.sin();
-
-        // Keep track of the distance the ray has traveled
-        let mut distance = 0.0;
-
-        // Maximum distance to prevent infinite loops
-        let max_distance = 30.0;
-
-        while distance < max_distance {
-            ray_x += step_x * 0.1;
-            ray_y += step_y * 0.1;
-            distance += 0.1;
-
-            let maze_x = ray_x as usize;
-            let maze_y = ray_y as usize;
-
-            // Check if the ray is out of bounds
-            if maze_y >= maze.len() || maze_x >= maze[0].len() {
-                break;
-            }
-
-            // Check if the ray has hit a wall
-            match maze[maze_y][maze_x] {
-                '+' | '-' | '|' => return (distance, Some(maze[maze_y][maze_x])),
-                _ => {}
-            }
-        }
-
-        // If no wall is hit, return maximum distance
-        (max_distance, None)
-    }
-
 }     
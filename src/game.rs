@@ -0,0 +1,356 @@
+use crate::collision::{move_and_slide, PLAYER_RADIUS};
+use crate::enemy::Enemy;
+use crate::health::Health;
+use crate::objectives::Objectives;
+use crate::player::{Movement, Player, PlayerCommand};
+
+pub const TICK_RATE: f32 = 60.0;
+pub const FIXED_DT: f32 = 1.0 / TICK_RATE;
+const MAX_TICKS_PER_FRAME: usize = 5; // Drop time instead of spiralling when frames are very slow
+
+const PICKUP_DISTANCE: f32 = 0.5;
+const CAT_HIT_DISTANCE: f32 = 0.5;
+const CHECKPOINT_MESSAGE_TIME: f32 = 2.0; // Seconds the HUD shows that a checkpoint was reached
+const KNOCKBACK_FRICTION: f32 = 8.0; // How fast a knockback slows down, per second
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Success,
+    Fail,
+}
+
+// The world as it was when the player reached a checkpoint cell
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub cell: (usize, usize),
+    pub reached_at: f32, // `World::elapsed` when it was reached
+    player: Player,
+    cats: Vec<Enemy>,
+    pickup_positions: Vec<na::Point3<f32>>,
+    pickups_collected: usize,
+    health: Health,
+}
+
+// Everything the simulation needs, with no window or images, so it can be
+// stepped deterministically
+pub struct World {
+    pub maze: Vec<Vec<char>>,
+    pub player: Player,
+    pub cats: Vec<Enemy>,
+    pub pickup_positions: Vec<na::Point3<f32>>, // Pickups still waiting to be collected
+    pub pickups_collected: usize,
+    pub pickups_total: usize,
+    pub elapsed: f32, // Seconds of play, counted in ticks
+    pub objectives: Objectives,
+    pub health: Health,
+    pub spawn_pose: (f32, f32, f32), // Where the player comes back after losing a life
+    pub checkpoint: Option<Checkpoint>,
+    knockback: (f32, f32), // Velocity the player is being pushed with, in cells per second
+    previous_pose: (f32, f32, f32), // Player x, y and angle before the last tick
+}
+
+impl World {
+    pub fn new(maze: Vec<Vec<char>>, player: Player, cats: Vec<Enemy>, pickup_positions: Vec<na::Point3<f32>>, objectives: Objectives, health: Health) -> Self {
+        let previous_pose = (player.x, player.y, player.angle);
+        let pickups_total = pickup_positions.len();
+        World {
+            maze,
+            player,
+            cats,
+            pickup_positions,
+            pickups_collected: 0,
+            pickups_total,
+            elapsed: 0.0,
+            objectives,
+            health,
+            spawn_pose: previous_pose,
+            checkpoint: None,
+            knockback: (0.0, 0.0),
+            previous_pose,
+        }
+    }
+
+    // Advances the simulation by one fixed tick
+    pub fn update(&mut self, command: &PlayerCommand, movement: &Movement) -> Option<Outcome> {
+        self.previous_pose = (self.player.x, self.player.y, self.player.angle);
+
+        self.player.walk(command.forward, command.strafe, command.sprint, FIXED_DT, &self.maze, movement);
+        let turn = command.turn * movement.turn_speed * FIXED_DT;
+        if turn < 0.0 {
+            self.player.turn_left(-turn);
+        } else {
+            self.player.turn_right(turn);
+        }
+
+        self.elapsed += FIXED_DT;
+        for cat in &mut self.cats {
+            cat.update(&self.maze, &self.player, FIXED_DT);
+        }
+        self.apply_cat_hits();
+
+        // Collect any pickup the player walks into
+        let player_pos = na::Point2::new(self.player.x, self.player.y);
+        let pickups_before = self.pickup_positions.len();
+        self.pickup_positions.retain(|pickup| (player_pos - na::Point2::new(pickup.x, pickup.y)).magnitude() >= PICKUP_DISTANCE);
+        self.pickups_collected += pickups_before - self.pickup_positions.len();
+
+        self.save_checkpoint_if_reached();
+
+        self.objectives.evaluate(self)
+    }
+
+    // Hurts the player when a cat touches them and pushes them away from it.
+    // Losing a life puts the player back at the spawn.
+    fn apply_cat_hits(&mut self) {
+        self.health.tick(FIXED_DT);
+
+        let (dx, dy) = (self.knockback.0 * FIXED_DT, self.knockback.1 * FIXED_DT);
+        (self.player.x, self.player.y) = move_and_slide(&self.maze, self.player.x, self.player.y, dx, dy, PLAYER_RADIUS);
+        let slowdown = (1.0 - KNOCKBACK_FRICTION * FIXED_DT).max(0.0);
+        self.knockback = (self.knockback.0 * slowdown, self.knockback.1 * slowdown);
+
+        let player_pos = na::Point2::new(self.player.x, self.player.y);
+        let Some(offset) = self
+            .cats
+            .iter()
+            .map(|cat| player_pos - na::Point2::new(cat.position.x, cat.position.y))
+            .find(|offset| offset.magnitude() < CAT_HIT_DISTANCE)
+        else {
+            return;
+        };
+        if self.health.is_invulnerable() {
+            return;
+        }
+
+        if self.health.take_hit() {
+            if !self.health.is_out_of_lives() {
+                self.respawn();
+            }
+        } else {
+            // Straight away from the cat, or backwards if standing right on it
+            let direction = if offset.magnitude() > f32::EPSILON {
+                offset.normalize()
+            } else {
+                na::Vector2::new(-self.player.angle.cos(), -self.player.angle.sin())
+            };
+            let speed = self.health.rules.knockback;
+            self.knockback = (direction.x * speed, direction.y * speed);
+        }
+    }
+
+    // Stepping onto a checkpoint cell other than the last one saves the world
+    fn save_checkpoint_if_reached(&mut self) {
+        let cell = (self.player.x as usize, self.player.y as usize);
+        let on_checkpoint = self.maze.get(cell.1).and_then(|row| row.get(cell.0)) == Some(&'k');
+        if !on_checkpoint || self.checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.cell == cell) {
+            return;
+        }
+        self.spawn_pose = (self.player.x, self.player.y, self.player.angle);
+        self.checkpoint = Some(Checkpoint {
+            cell,
+            reached_at: self.elapsed,
+            player: self.player.clone(),
+            cats: self.cats.clone(),
+            pickup_positions: self.pickup_positions.clone(),
+            pickups_collected: self.pickups_collected,
+            health: self.health.clone(),
+        });
+    }
+
+    pub fn showing_checkpoint_message(&self) -> bool {
+        self.checkpoint.as_ref().is_some_and(|checkpoint| self.elapsed - checkpoint.reached_at < CHECKPOINT_MESSAGE_TIME)
+    }
+
+    // Puts the world back the way it was at the last checkpoint, clock
+    // included. Returns false if no checkpoint was reached.
+    pub fn restore_checkpoint(&mut self) -> bool {
+        let Some(checkpoint) = self.checkpoint.clone() else {
+            return false;
+        };
+        self.player = checkpoint.player;
+        self.cats = checkpoint.cats;
+        for cat in &mut self.cats {
+            cat.forget_player(); // Otherwise a cat that was chasing would catch the player again at once
+        }
+        self.pickup_positions = checkpoint.pickup_positions;
+        self.pickups_collected = checkpoint.pickups_collected;
+        self.health = checkpoint.health;
+        self.elapsed = checkpoint.reached_at;
+        self.respawn();
+        true
+    }
+
+    fn respawn(&mut self) {
+        let (x, y, angle) = self.spawn_pose;
+        self.place_player(x, y, angle);
+    }
+
+    // Moves the player at rest, jumping there instead of sliding across the maze
+    pub fn place_player(&mut self, x: f32, y: f32, angle: f32) {
+        self.player.x = x;
+        self.player.y = y;
+        self.player.angle = angle;
+        self.player.velocity = (0.0, 0.0);
+        self.knockback = (0.0, 0.0);
+        self.previous_pose = (x, y, angle);
+    }
+
+    // Mouse look turns the camera right away instead of waiting for the next tick
+    pub fn turn_camera(&mut self, angle: f32) {
+        self.player.angle += angle;
+        self.previous_pose.2 += angle;
+    }
+
+    // The player as it should be drawn `alpha` of the way between the last two ticks
+    pub fn interpolated_player(&self, alpha: f32) -> Player {
+        let (prev_x, prev_y, prev_angle) = self.previous_pose;
+        let mut player = self.player.clone();
+        player.x = prev_x + (self.player.x - prev_x) * alpha;
+        player.y = prev_y + (self.player.y - prev_y) * alpha;
+        player.angle = prev_angle + (self.player.angle - prev_angle) * alpha;
+        player
+    }
+}
+
+// Turns variable frame times into a whole number of fixed ticks
+#[derive(Default)]
+pub struct FixedTimestep {
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    // Returns how many ticks to run for a frame that took `frame_time` seconds
+    pub fn advance(&mut self, frame_time: f32) -> usize {
+        self.accumulator += frame_time;
+        let ticks = (self.accumulator / FIXED_DT) as usize;
+        self.accumulator -= ticks as f32 * FIXED_DT;
+
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.accumulator = 0.0;
+            MAX_TICKS_PER_FRAME
+        } else {
+            ticks
+        }
+    }
+
+    // How far the leftover time is into the next tick, from 0 to 1
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / FIXED_DT).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::EnemyKind;
+    use crate::health::Difficulty;
+    use crate::objectives::{FailCondition, Objective};
+
+    // A straight corridor with the goal at its east end
+    fn corridor(objectives: Objectives) -> World {
+        let maze: Vec<Vec<char>> = ["+------+", "|     g|", "+------+"].iter().map(|row| row.chars().collect()).collect();
+        let player = Player::new(1.5, 1.5, std::f32::consts::PI / 3.0);
+        World::new(maze, player, Vec::new(), Vec::new(), objectives, Health::new(Difficulty::Normal.rules()))
+    }
+
+    fn forward() -> PlayerCommand {
+        PlayerCommand { forward: 1.0, ..PlayerCommand::default() }
+    }
+
+    #[test]
+    fn walking_moves_by_walk_speed_per_second() {
+        let mut world = corridor(Objectives::default());
+        let movement = Movement::default();
+        for _ in 0..30 {
+            assert_eq!(world.update(&forward(), &movement), None);
+        }
+        // Half a second east at walk speed, without drifting off the row
+        assert!((world.player.x - (1.5 + movement.walk_speed * 0.5)).abs() < 1e-3, "x = {}", world.player.x);
+        assert!((world.player.y - 1.5).abs() < 1e-6);
+        assert!((world.elapsed - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn reaching_the_goal_succeeds_on_the_same_tick_every_run() {
+        let run = || {
+            let mut world = corridor(Objectives::default());
+            (1..=200).find_map(|tick| world.update(&forward(), &Movement::default()).map(|outcome| (tick, outcome)))
+        };
+        let (tick, outcome) = run().expect("the goal is reached");
+        assert_eq!(outcome, Outcome::Success);
+        // The goal cell starts at x = 6, 4.5 cells from the spawn at 3 cells per second
+        assert_eq!(tick, 90);
+        assert_eq!(run(), Some((tick, outcome)));
+    }
+
+    #[test]
+    fn standing_still_runs_out_the_time_limit() {
+        let objectives = Objectives { win: vec![Objective::ReachGoal], fail: vec![FailCondition::TimeLimit(1.0)] };
+        let mut world = corridor(objectives);
+        let idle = PlayerCommand::default();
+        let outcomes: Vec<Option<Outcome>> = (0..61).map(|_| world.update(&idle, &Movement::default())).collect();
+        assert!(outcomes[..59].iter().all(Option::is_none));
+        assert_eq!(outcomes.last(), Some(&Some(Outcome::Fail)));
+        assert_eq!((world.player.x, world.player.y), (1.5, 1.5));
+    }
+
+    #[test]
+    fn smooth_movement_speeds_up_and_slides_to_a_stop() {
+        let mut world = corridor(Objectives { win: vec![Objective::Survive(60.0)], fail: Vec::new() });
+        let movement = Movement { inertia: true, ..Movement::default() };
+        world.update(&forward(), &movement);
+        assert!(world.player.velocity.0 > 0.0 && world.player.velocity.0 < movement.walk_speed);
+        for _ in 0..30 {
+            world.update(&forward(), &movement);
+        }
+        assert!((world.player.velocity.0 - movement.walk_speed).abs() < 0.05);
+
+        // Letting go keeps the player moving for a moment
+        let x = world.player.x;
+        world.update(&PlayerCommand::default(), &movement);
+        assert!(world.player.x > x);
+        assert!(world.player.velocity.0 < movement.walk_speed);
+    }
+
+    #[test]
+    fn losing_the_last_life_fails_without_a_caught_line() {
+        let mut world = corridor(Objectives { win: vec![Objective::Survive(60.0)], fail: Vec::new() });
+        world.cats.push(Enemy::new(EnemyKind::Cat, na::Point3::new(1.7, 1.5, 0.0), Vec::new(), 0.0));
+        (world.health.health, world.health.lives) = (1, 1);
+        assert_eq!(world.update(&PlayerCommand::default(), &Movement::default()), Some(Outcome::Fail));
+        assert!(world.health.is_out_of_lives());
+    }
+
+    #[test]
+    fn walls_stop_the_player() {
+        let mut world = corridor(Objectives { win: vec![Objective::Survive(60.0)], fail: Vec::new() });
+        world.player.angle = std::f32::consts::PI; // Facing the west wall
+        for _ in 0..60 {
+            world.update(&forward(), &Movement::default());
+        }
+        assert!((world.player.x - (1.0 + PLAYER_RADIUS)).abs() < 1e-3, "x = {}", world.player.x);
+    }
+
+    #[test]
+    fn fixed_timestep_carries_leftover_time() {
+        let mut timestep = FixedTimestep::default();
+        // 1.2 ticks a frame: one tick each, with the remainder building up
+        for expected_alpha in [0.2, 0.4, 0.6, 0.8] {
+            assert_eq!(timestep.advance(FIXED_DT * 1.2), 1);
+            assert!((timestep.alpha() - expected_alpha).abs() < 1e-3, "alpha = {}", timestep.alpha());
+        }
+        // The leftover 0.8 plus 0.6 makes one more tick
+        assert_eq!(timestep.advance(FIXED_DT * 0.6), 1);
+        assert!((timestep.alpha() - 0.4).abs() < 1e-3);
+        assert_eq!(timestep.advance(FIXED_DT * 0.3), 0);
+        assert!((timestep.alpha() - 0.7).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fixed_timestep_drops_time_after_a_long_frame() {
+        let mut timestep = FixedTimestep::default();
+        assert_eq!(timestep.advance(FIXED_DT * 20.5), MAX_TICKS_PER_FRAME);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(FIXED_DT * 0.5), 0);
+    }
+}
@@ -0,0 +1,571 @@
+// Random mazes written in the same ASCII style as the level files: every maze
+// cell is two characters wide between `+--+` corners and `|` walls. Mazes are
+// perfect, with exactly one way between any two cells, so the goal can always
+// be reached.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CELL_WIDTH: usize = 3; // Characters per cell column, counting the wall on its left
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Algorithm {
+    Backtracker, // Long winding corridors with few branches
+    Prim,        // Many short dead ends branching off everywhere
+    Kruskal,     // Evenly mixed, without any bias
+    Wilson,      // Picked uniformly from every possible maze
+}
+
+pub const ALGORITHMS: [Algorithm; 4] = [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Kruskal, Algorithm::Wilson];
+
+impl Algorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Backtracker => "backtracker",
+            Algorithm::Prim => "prim",
+            Algorithm::Kruskal => "kruskal",
+            Algorithm::Wilson => "wilson",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Algorithm> {
+        ALGORITHMS.iter().copied().find(|algorithm| algorithm.name() == value)
+    }
+}
+
+// SplitMix64, so the same seed gives the same maze on every machine
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    // A number from 0 up to but not including `n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// Which walls between neighbouring cells are open, with cells numbered row by row
+pub struct Maze {
+    pub width: usize,
+    pub height: usize,
+    open_east: Vec<bool>,
+    open_south: Vec<bool>,
+}
+
+impl Maze {
+    pub fn generate(algorithm: Algorithm, width: usize, height: usize, rng: &mut Rng) -> Maze {
+        let mut maze = Maze {
+            width: width.max(1),
+            height: height.max(1),
+            open_east: vec![false; width.max(1) * height.max(1)],
+            open_south: vec![false; width.max(1) * height.max(1)],
+        };
+        match algorithm {
+            Algorithm::Backtracker => maze.carve_backtracker(rng),
+            Algorithm::Prim => maze.carve_prim(rng),
+            Algorithm::Kruskal => maze.carve_kruskal(rng),
+            Algorithm::Wilson => maze.carve_wilson(rng),
+        }
+        maze
+    }
+
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let (x, y) = (cell % self.width, cell / self.width);
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push(cell - 1);
+        }
+        if x + 1 < self.width {
+            neighbours.push(cell + 1);
+        }
+        if y > 0 {
+            neighbours.push(cell - self.width);
+        }
+        if y + 1 < self.height {
+            neighbours.push(cell + self.width);
+        }
+        neighbours
+    }
+
+    fn is_open(&self, a: usize, b: usize) -> bool {
+        let (low, high) = (a.min(b), a.max(b));
+        if high == low + 1 {
+            self.open_east[low]
+        } else {
+            self.open_south[low]
+        }
+    }
+
+    // Knocks down the wall between two neighbouring cells
+    fn carve(&mut self, a: usize, b: usize) {
+        let (low, high) = (a.min(b), a.max(b));
+        if high == low + 1 {
+            self.open_east[low] = true;
+        } else {
+            self.open_south[low] = true;
+        }
+    }
+
+    // Cells reachable through open walls
+    pub fn passages(&self, cell: usize) -> Vec<usize> {
+        self.neighbours(cell).into_iter().filter(|&other| self.is_open(cell, other)).collect()
+    }
+
+    // Steps from `start` to every cell
+    pub fn distances(&self, start: usize) -> Vec<usize> {
+        let mut distances = vec![usize::MAX; self.cell_count()];
+        distances[start] = 0;
+        let mut queue = VecDeque::from([start]);
+        while let Some(cell) = queue.pop_front() {
+            for next in self.passages(cell) {
+                if distances[next] == usize::MAX {
+                    distances[next] = distances[cell] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    // Depth-first search that backs up at dead ends
+    fn carve_backtracker(&mut self, rng: &mut Rng) {
+        let mut visited = vec![false; self.cell_count()];
+        let start = rng.below(self.cell_count());
+        let mut stack = vec![start];
+        visited[start] = true;
+        while let Some(&cell) = stack.last() {
+            let unvisited: Vec<usize> = self.neighbours(cell).into_iter().filter(|&next| !visited[next]).collect();
+            if unvisited.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let next = rng.pick(&unvisited);
+            self.carve(cell, next);
+            visited[next] = true;
+            stack.push(next);
+        }
+    }
+
+    // Grows the maze from one cell through a random wall on its edge at a time
+    fn carve_prim(&mut self, rng: &mut Rng) {
+        let mut in_maze = vec![false; self.cell_count()];
+        let start = rng.below(self.cell_count());
+        in_maze[start] = true;
+        let mut frontier: Vec<(usize, usize)> = self.neighbours(start).into_iter().map(|next| (start, next)).collect();
+        while !frontier.is_empty() {
+            let (from, to) = frontier.swap_remove(rng.below(frontier.len()));
+            if in_maze[to] {
+                continue;
+            }
+            self.carve(from, to);
+            in_maze[to] = true;
+            frontier.extend(self.neighbours(to).into_iter().filter(|&next| !in_maze[next]).map(|next| (to, next)));
+        }
+    }
+
+    // Opens walls in random order unless both sides are already connected
+    fn carve_kruskal(&mut self, rng: &mut Rng) {
+        let mut walls = Vec::new();
+        for cell in 0..self.cell_count() {
+            walls.extend(self.neighbours(cell).into_iter().filter(|&next| next > cell).map(|next| (cell, next)));
+        }
+        rng.shuffle(&mut walls);
+
+        let mut sets = DisjointSets::new(self.cell_count());
+        for (a, b) in walls {
+            if sets.union(a, b) {
+                self.carve(a, b);
+            }
+        }
+    }
+
+    // Random walks from cells outside the maze until they hit it, keeping the
+    // walk with its loops erased
+    fn carve_wilson(&mut self, rng: &mut Rng) {
+        let mut in_maze = vec![false; self.cell_count()];
+        in_maze[rng.below(self.cell_count())] = true;
+        let mut next_step = vec![0; self.cell_count()];
+
+        for start in 0..self.cell_count() {
+            if in_maze[start] {
+                continue;
+            }
+            // Walking over an earlier step overwrites where it led, erasing the loop
+            let mut cell = start;
+            while !in_maze[cell] {
+                let next = rng.pick(&self.neighbours(cell));
+                next_step[cell] = next;
+                cell = next;
+            }
+
+            let mut cell = start;
+            while !in_maze[cell] {
+                in_maze[cell] = true;
+                self.carve(cell, next_step[cell]);
+                cell = next_step[cell];
+            }
+        }
+    }
+
+    // The maze in the level file format, one character per game cell
+    pub fn to_ascii(&self) -> Vec<Vec<char>> {
+        let columns = self.width * CELL_WIDTH + 1;
+        let mut grid = vec![vec![' '; columns]; self.height * 2 + 1];
+        for (row, line) in grid.iter_mut().enumerate() {
+            for (column, ch) in line.iter_mut().enumerate() {
+                let on_corner_column = column % CELL_WIDTH == 0;
+                *ch = match (row % 2 == 0, on_corner_column) {
+                    (true, true) => '+',
+                    (true, false) => '-',
+                    (false, true) => '|',
+                    (false, false) => ' ',
+                };
+            }
+        }
+
+        for cell in 0..self.cell_count() {
+            let (x, y) = (cell % self.width, cell / self.width);
+            let (row, column) = ascii_position(cell, self.width);
+            if x + 1 < self.width && self.open_east[cell] {
+                grid[row][column + 2] = ' ';
+            }
+            if y + 1 < self.height && self.open_south[cell] {
+                grid[row + 1][column] = ' ';
+                grid[row + 1][column + 1] = ' ';
+            }
+        }
+        grid
+    }
+}
+
+// Row and column of the first of a cell's two characters
+pub fn ascii_position(cell: usize, width: usize) -> (usize, usize) {
+    let (x, y) = (cell % width, cell / width);
+    (y * 2 + 1, x * CELL_WIDTH + 1)
+}
+
+// Union-find over cell numbers, for Kruskal's algorithm
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(count: usize) -> Self {
+        DisjointSets { parents: (0..count).collect() }
+    }
+
+    fn find(&mut self, item: usize) -> usize {
+        let mut root = item;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut item = item;
+        while self.parents[item] != root {
+            let next = self.parents[item];
+            self.parents[item] = root;
+            item = next;
+        }
+        root
+    }
+
+    // Joins the sets holding `a` and `b`, returning false if they were already one
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        self.parents[root_a] = root_b;
+        true
+    }
+}
+
+// What to generate
+#[derive(Clone, Copy)]
+pub struct GeneratorOptions {
+    pub algorithm: Algorithm,
+    pub seed: u64,
+    pub width: usize, // In maze cells; the level is 3 characters per cell plus one wide
+    pub height: usize, // In maze cells; the level is 2 characters per cell plus one tall
+    pub cats: usize,
+    pub cheshires: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            algorithm: Algorithm::Backtracker,
+            seed: 0,
+            width: 6,
+            height: 5,
+            cats: 1,
+            cheshires: 0,
+        }
+    }
+}
+
+// A complete level file: the player starts in the top-left cell and the goal
+// is the cell farthest from it. Cats patrol a few cells along the corridors,
+// away from the start.
+pub fn level_file(options: &GeneratorOptions, name: &str) -> String {
+    let mut rng = Rng::new(options.seed);
+    let maze = Maze::generate(options.algorithm, options.width, options.height, &mut rng);
+    let mut grid = maze.to_ascii();
+
+    let distances = maze.distances(0);
+    let goal = (0..distances.len()).max_by_key(|&cell| distances[cell]).unwrap_or(0);
+    let (row, column) = ascii_position(0, maze.width);
+    grid[row][column] = if maze.passages(0).contains(&1) { '>' } else { 'v' };
+    let (row, column) = ascii_position(goal, maze.width);
+    grid[row][column + 1] = 'g';
+
+    let mut contents = format!(
+        "# Generated maze: {}, seed {}\nname = {}\nenemy_texture = image/card.jpeg\ncheshire_texture = image/cat.png\nwall_texture = image/wall.jpeg\nobjective = reach_goal\nfail = caught\n",
+        options.algorithm.name(),
+        options.seed,
+        name
+    );
+
+    // Keep cats out of the first few cells so the player is not caught at once
+    let candidates: Vec<usize> = (0..distances.len()).filter(|&cell| distances[cell] >= 3 && cell != goal).collect();
+    for index in 0..options.cats + options.cheshires {
+        if candidates.is_empty() {
+            break;
+        }
+        let key = if index < options.cats { "cat" } else { "cheshire" };
+        let patrol = patrol(&maze, rng.pick(&candidates), &mut rng);
+        let points: Vec<String> = patrol.iter().map(|&cell| cell_centre(cell, maze.width)).collect();
+        contents += &format!("{} = {}\n", key, points.join("; "));
+    }
+
+    contents += "---\n";
+    for line in grid {
+        contents.extend(line);
+        contents.push('\n');
+    }
+    contents
+}
+
+// A short walk along the passages from `start`, without turning back
+fn patrol(maze: &Maze, start: usize, rng: &mut Rng) -> Vec<usize> {
+    let mut cells = vec![start];
+    for _ in 0..1 + rng.below(3) {
+        let current = cells[cells.len() - 1];
+        let onward: Vec<usize> = maze.passages(current).into_iter().filter(|next| !cells.contains(next)).collect();
+        if onward.is_empty() {
+            break;
+        }
+        cells.push(rng.pick(&onward));
+    }
+    cells
+}
+
+// Centre of a maze cell's two characters, in game coordinates
+fn cell_centre(cell: usize, width: usize) -> String {
+    let (row, column) = ascii_position(cell, width);
+    format!("{}, {}", column + 1, row as f32 + 0.5)
+}
+
+// The `generate` subcommand: prints a level file built from the given options
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = parse_args(args)?;
+    print!("{}", level_file(&options, &format!("Generated {}", options.seed)));
+    Ok(())
+}
+
+// `--algorithm NAME`, `--seed N`, `--size WxH`, `--cats N` and `--cheshires N`
+pub fn parse_args(args: &[String]) -> Result<GeneratorOptions, Box<dyn Error>> {
+    let mut options = GeneratorOptions::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value after {}", flag))?;
+        let invalid = || format!("invalid value '{}' for {}", value, flag);
+        match flag.as_str() {
+            "--algorithm" => options.algorithm = Algorithm::parse(value).ok_or_else(invalid)?,
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--size" => {
+                let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+                options.width = width.parse().map_err(|_| invalid())?;
+                options.height = height.parse().map_err(|_| invalid())?;
+                if options.width < 2 || options.height < 2 {
+                    return Err(format!("{} must be at least 2x2", flag).into());
+                }
+            }
+            "--cats" => options.cats = value.parse().map_err(|_| invalid())?,
+            "--cheshires" => options.cheshires = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown option '{}'", flag).into()),
+        }
+    }
+    Ok(options)
+}
+
+// Endless mode: one generated maze after another, growing with each round
+#[derive(Clone, Copy)]
+pub struct EndlessRun {
+    pub seed: u64,
+    pub round: usize, // Counting from 1
+    pub algorithm: Option<Algorithm>, // Cycles through every algorithm when `None`
+}
+
+impl EndlessRun {
+    pub fn new(seed: u64, algorithm: Option<Algorithm>) -> Self {
+        EndlessRun { seed, round: 1, algorithm }
+    }
+
+    pub fn next(&self) -> Self {
+        EndlessRun { round: self.round + 1, ..*self }
+    }
+
+    pub fn options(&self) -> GeneratorOptions {
+        let round = self.round;
+        GeneratorOptions {
+            algorithm: self.algorithm.unwrap_or(ALGORITHMS[(round - 1) % ALGORITHMS.len()]),
+            seed: self.seed.wrapping_add(round as u64),
+            width: (3 + round).min(10),
+            height: (3 + round / 2).min(8),
+            cats: round.min(6),
+            cheshires: round / 3,
+        }
+    }
+
+    pub fn level_file(&self) -> String {
+        level_file(&self.options(), &format!("Endless round {}", self.round))
+    }
+
+    // The `endless` subcommand takes `--seed N` and `--algorithm NAME`, both optional
+    pub fn parse_args(args: &[String]) -> Result<EndlessRun, Box<dyn Error>> {
+        let mut run = EndlessRun::new(random_seed(), None);
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or_else(|| format!("missing value after {}", flag))?;
+            let invalid = || format!("invalid value '{}' for {}", value, flag);
+            match flag.as_str() {
+                "--algorithm" => run.algorithm = Some(Algorithm::parse(value).ok_or_else(invalid)?),
+                "--seed" => run.seed = value.parse().map_err(|_| invalid())?,
+                _ => return Err(format!("unknown option '{}'", flag).into()),
+            }
+        }
+        Ok(run)
+    }
+}
+
+// A different seed every run, for when none is given
+pub fn random_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::LevelFile;
+    use crate::validate::validate;
+
+    const SIZES: [(usize, usize); 4] = [(2, 2), (3, 7), (6, 5), (10, 8)];
+    const SEEDS: std::ops::Range<u64> = 0..25;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn generated_levels_validate() {
+        for algorithm in ALGORITHMS {
+            for (width, height) in SIZES {
+                for seed in SEEDS {
+                    let options = GeneratorOptions { algorithm, seed, width, height, cats: 3, cheshires: 1 };
+                    let contents = level_file(&options, "Test");
+                    let level = LevelFile::parse_contents("generated.txt", &contents)
+                        .unwrap_or_else(|err| panic!("{} {}x{} seed {}: {}\n{}", algorithm.name(), width, height, seed, err, contents));
+                    let diagnostics: Vec<String> = validate(&level).iter().map(|diagnostic| diagnostic.to_string()).collect();
+                    assert!(diagnostics.is_empty(), "{} {}x{} seed {}: {:?}\n{}", algorithm.name(), width, height, seed, diagnostics, contents);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn endless_rounds_validate() {
+        let mut run = EndlessRun::new(3, None);
+        for _ in 0..12 {
+            let level = LevelFile::parse_contents("endless.txt", &run.level_file()).unwrap();
+            assert!(validate(&level).is_empty(), "round {}", run.round);
+            run = run.next();
+        }
+    }
+
+    #[test]
+    fn mazes_are_perfect() {
+        for algorithm in ALGORITHMS {
+            for (width, height) in SIZES {
+                for seed in SEEDS {
+                    let maze = Maze::generate(algorithm, width, height, &mut Rng::new(seed));
+                    // Connected with one passage fewer than cells means a tree:
+                    // exactly one way between any two cells
+                    let passages: usize = (0..maze.cell_count()).map(|cell| maze.passages(cell).len()).sum();
+                    assert_eq!(passages / 2, maze.cell_count() - 1, "{} {}x{} seed {}", algorithm.name(), width, height, seed);
+                    assert!(maze.distances(0).iter().all(|&distance| distance != usize::MAX), "{} {}x{} seed {}", algorithm.name(), width, height, seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_maze() {
+        for algorithm in ALGORITHMS {
+            let generate = |seed| Maze::generate(algorithm, 8, 6, &mut Rng::new(seed)).to_ascii();
+            assert!(generate(7) == generate(7), "{}", algorithm.name());
+            assert!(generate(7) != generate(8), "{}", algorithm.name());
+
+            let options = GeneratorOptions { algorithm, seed: 7, ..GeneratorOptions::default() };
+            assert_eq!(level_file(&options, "Test"), level_file(&options, "Test"));
+        }
+    }
+
+    #[test]
+    fn parse_args_reads_every_option() {
+        let options = parse_args(&args(&["--algorithm", "wilson", "--seed", "9", "--size", "2x2", "--cats", "0", "--cheshires", "2"])).unwrap();
+        assert_eq!(options.algorithm, Algorithm::Wilson);
+        assert_eq!((options.seed, options.width, options.height, options.cats, options.cheshires), (9, 2, 2, 0, 2));
+    }
+
+    #[test]
+    fn parse_args_rejects_sizes_below_2x2() {
+        for size in ["1x5", "5x1", "0x0"] {
+            let err = parse_args(&args(&["--size", size])).err().unwrap();
+            assert_eq!(err.to_string(), "--size must be at least 2x2");
+        }
+        assert!(parse_args(&args(&["--size", "5"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_rejects_unknown_algorithms() {
+        let err = parse_args(&args(&["--algorithm", "eller"])).err().unwrap();
+        assert_eq!(err.to_string(), "invalid value 'eller' for --algorithm");
+        assert!(EndlessRun::parse_args(&args(&["--algorithm", "eller"])).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+// How much punishment the player can take before the level is lost
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+pub const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+// Health values for one difficulty
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HealthRules {
+    pub max_health: u32,
+    pub lives: u32,
+    pub damage: u32,            // Health lost each time a cat touches the player
+    pub invulnerable_time: f32, // Seconds after a hit during which cats cannot hurt again
+    pub knockback: f32,         // Cells per second the player is pushed away from the cat
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Difficulty> {
+        DIFFICULTIES.iter().copied().find(|difficulty| difficulty.name() == value)
+    }
+
+    pub fn rules(&self) -> HealthRules {
+        match self {
+            Difficulty::Easy => HealthRules { max_health: 5, lives: 5, damage: 1, invulnerable_time: 2.0, knockback: 6.0 },
+            Difficulty::Normal => HealthRules { max_health: 3, lives: 3, damage: 1, invulnerable_time: 1.5, knockback: 5.0 },
+            Difficulty::Hard => HealthRules { max_health: 2, lives: 1, damage: 1, invulnerable_time: 1.0, knockback: 4.0 },
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Health {
+    pub rules: HealthRules,
+    pub health: u32,
+    pub lives: u32,
+    pub hits_taken: u32,
+    invulnerable: f32, // Seconds of invulnerability left
+}
+
+impl Health {
+    pub fn new(rules: HealthRules) -> Self {
+        Health {
+            rules,
+            health: rules.max_health,
+            lives: rules.lives,
+            hits_taken: 0,
+            invulnerable: 0.0,
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    pub fn is_out_of_lives(&self) -> bool {
+        self.lives == 0
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+    }
+
+    // Takes a hit unless still invulnerable from the last one. Returns true if
+    // the hit cost a life, in which case the health is full again for the
+    // next life.
+    pub fn take_hit(&mut self) -> bool {
+        if self.is_invulnerable() || self.is_out_of_lives() {
+            return false;
+        }
+        self.hits_taken += 1;
+        self.invulnerable = self.rules.invulnerable_time;
+        self.health = self.health.saturating_sub(self.rules.damage);
+        if self.health > 0 {
+            return false;
+        }
+
+        self.lives -= 1;
+        if self.lives > 0 {
+            self.health = self.rules.max_health;
+        }
+        true
+    }
+
+    // Blinks on and off five times a second while invulnerable
+    pub fn is_flashing(&self) -> bool {
+        self.is_invulnerable() && (self.invulnerable * 10.0) as u32 % 2 == 1
+    }
+}
@@ -1,49 +1,185 @@
 extern crate minifb;
-use minifb::{Key, Window};
-use crate::player::Player;
+use minifb::{InputCallback, Key, KeyRepeat, MouseButton, MouseMode, Window};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use crate::controls::{Action, Controls, BINDABLE_KEYS};
+use crate::player::PlayerCommand;
+use crate::settings::{Settings, MOUSE_SENSITIVITY_SCALE};
+use crate::ui::UiInput;
 
-const MOVE_SPEED: f32 = 0.1;
-const TURN_SPEED: f32 = std::f32::consts::PI / 30.0;
+pub fn player_command(input: &Input) -> PlayerCommand {
+    let axis = |positive: Action, negative: Action| {
+        input.is_action_held(positive) as i32 as f32 - input.is_action_held(negative) as i32 as f32
+    };
 
-pub fn process_events(window: &Window, player: &mut Player, maze: &[Vec<char>], prev_mouse_x: &mut Option<f32>) {
-    let move_speed = 0.1; // Adjust the speed to suit your game
-    let turn_speed = 0.05;
+    PlayerCommand {
+        forward: axis(Action::MoveForward, Action::MoveBackward),
+        strafe: axis(Action::StrafeRight, Action::StrafeLeft),
+        turn: axis(Action::TurnRight, Action::TurnLeft),
+        sprint: input.is_action_held(Action::Sprint),
+    }
+}
+
+// Turns the player from relative mouse movement while the mouse is captured.
+// minifb can hide the cursor but cannot warp it back to the centre, so turning
+// still stops once the hidden cursor reaches the edge of the screen.
+#[derive(Default)]
+pub struct MouseLook {
+    captured: bool,
+    prev_x: Option<f32>,
+}
 
-    if window.is_key_down(Key::W) {
-        player.move_forward(move_speed, maze);
+impl MouseLook {
+    pub fn is_captured(&self) -> bool {
+        self.captured
     }
 
-    if window.is_key_down(Key::S) {
-        player.move_backward(move_speed, maze);
+    pub fn capture(&mut self, window: &mut Window) {
+        self.captured = true;
+        self.prev_x = None; // Do not turn by however far the cursor moved while released
+        window.set_cursor_visibility(false);
     }
 
-    if window.is_key_down(Key::A) {
-        player.turn_left(turn_speed);
+    pub fn release(&mut self, window: &mut Window) {
+        self.captured = false;
+        window.set_cursor_visibility(true);
     }
 
-    if window.is_key_down(Key::D) {
-        player.turn_right(turn_speed);
+    // Angle to turn by this frame
+    pub fn turn(&mut self, input: &Input, settings: &Settings) -> f32 {
+        if !self.captured {
+            return 0.0;
+        }
+
+        let mouse_x = match input.raw_mouse_pos {
+            Some((x, _)) => x,
+            None => return 0.0,
+        };
+        let delta_x = mouse_x - self.prev_x.unwrap_or(mouse_x);
+        self.prev_x = Some(mouse_x);
+
+        let direction = if settings.invert_mouse { -1.0 } else { 1.0 };
+        delta_x * settings.mouse_sensitivity * MOUSE_SENSITIVITY_SCALE * direction
     }
+}
 
-    // Mouse-based horizontal movement
-    if let Some((mouse_x, _mouse_y)) = window.get_mouse_pos(minifb::MouseMode::Pass) {
-        if let Some(prev_x) = *prev_mouse_x {
-            let delta_x = mouse_x - prev_x;
+// Keyboard and mouse state for one frame. `update` is called once per frame so
+// every game state sees the same presses, and a single press is only reported once
+#[derive(Default)]
+pub struct Input {
+    pub controls: Controls,
+    held: HashSet<Key>,
+    pressed: HashSet<Key>,  // Went down this frame
+    repeated: HashSet<Key>, // Went down this frame or is auto-repeating
+    released: HashSet<Key>, // Went up this frame
+    pub mouse_pos: Option<(f32, f32)>,     // Only while the cursor is inside the window
+    pub raw_mouse_pos: Option<(f32, f32)>, // Keeps tracking outside the window
+    mouse_down: bool,
+    mouse_clicked: bool,
+    typed: Vec<char>,
+    typed_buffer: Rc<RefCell<Vec<char>>>, // Filled by the window's character callback
+}
 
-            // Adjust the player's angle based on mouse movement
-            player.angle += delta_x * TURN_SPEED;
+struct TypedChars(Rc<RefCell<Vec<char>>>);
+
+impl InputCallback for TypedChars {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(ch) = char::from_u32(uni_char) {
+            self.0.borrow_mut().push(ch);
         }
+    }
+}
+
+impl Input {
+    pub fn new(window: &mut Window, controls: Controls) -> Self {
+        let input = Input { controls, ..Input::default() };
+        window.set_input_callback(Box::new(TypedChars(Rc::clone(&input.typed_buffer))));
+        input
+    }
+
+    pub fn update(&mut self, window: &Window) {
+        let to_set = |keys: Option<Vec<Key>>| keys.unwrap_or_default().into_iter().collect::<HashSet<Key>>();
+        self.held = to_set(window.get_keys());
+        self.pressed = to_set(window.get_keys_pressed(KeyRepeat::No));
+        self.repeated = to_set(window.get_keys_pressed(KeyRepeat::Yes));
+        self.released = to_set(window.get_keys_released());
+
+        self.mouse_pos = window.get_mouse_pos(MouseMode::Discard);
+        self.raw_mouse_pos = window.get_mouse_pos(MouseMode::Pass);
+
+        let mouse_down = window.get_mouse_down(MouseButton::Left);
+        self.mouse_clicked = mouse_down && !self.mouse_down;
+        self.mouse_down = mouse_down;
+
+        self.typed = self.typed_buffer.borrow_mut().drain(..).collect();
+    }
+
+    pub fn is_held(&self, key: Key) -> bool {
+        self.held.contains(&key)
+    }
 
-        // Update the previous mouse position
-        *prev_mouse_x = Some(mouse_x);
+    pub fn is_pressed(&self, key: Key) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    // Like `is_pressed`, but also true while the key auto-repeats (menu navigation)
+    pub fn is_repeated(&self, key: Key) -> bool {
+        self.repeated.contains(&key)
+    }
+
+    pub fn is_released(&self, key: Key) -> bool {
+        self.released.contains(&key)
+    }
+
+    pub fn is_mouse_clicked(&self) -> bool {
+        self.mouse_clicked
+    }
+
+    // A key that went down this frame, used when rebinding controls. Only keys
+    // the controls file can name count, and when several go down together the
+    // one listed first in `BINDABLE_KEYS` wins.
+    pub fn first_pressed_key(&self) -> Option<Key> {
+        BINDABLE_KEYS.iter().copied().find(|&key| self.is_pressed(key))
+    }
+
+    pub fn is_action_held(&self, action: Action) -> bool {
+        self.controls.keys(action).iter().any(|&key| self.is_held(key))
+    }
+
+    pub fn is_action_pressed(&self, action: Action) -> bool {
+        self.controls.keys(action).iter().any(|&key| self.is_pressed(key))
+    }
+
+    pub fn is_action_repeated(&self, action: Action) -> bool {
+        self.controls.keys(action).iter().any(|&key| self.is_repeated(key))
+    }
+
+    pub fn ui_input(&self) -> UiInput {
+        UiInput {
+            up: self.is_action_repeated(Action::MenuUp),
+            down: self.is_action_repeated(Action::MenuDown),
+            left: self.is_action_repeated(Action::MenuLeft),
+            right: self.is_action_repeated(Action::MenuRight),
+            activate: self.is_action_pressed(Action::Use),
+            backspace: self.is_repeated(Key::Backspace),
+            typed: self.typed.clone(),
+            mouse_pos: self.mouse_pos,
+            mouse_clicked: self.mouse_clicked,
+        }
     }
 }
 
-fn is_wall(maze: &[Vec<char>], x: f32, y: f32) -> bool {
-    let cell_x = x as usize;
-    let cell_y = y as usize;
-    if cell_x >= maze[0].len() || cell_y >= maze.len() {
-        return true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_pressed_key_skips_unbindable_keys_and_picks_in_list_order() {
+        let mut input = Input { pressed: [Key::F1, Key::Space, Key::Z, Key::C].into_iter().collect(), ..Input::default() };
+        assert_eq!(input.first_pressed_key(), Some(Key::C));
+
+        input.pressed = [Key::F1].into_iter().collect();
+        assert_eq!(input.first_pressed_key(), None);
     }
-    maze[cell_y][cell_x] == '+'
-}
\ No newline at end of file
+}
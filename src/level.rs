@@ -1,81 +1,294 @@
-extern crate nalgebra as na;
-
 use std::error::Error;
+use std::fs;
+use std::f32::consts::PI;
+use std::path::Path;
 
-pub struct Level {
+use crate::assets::Image;
+use crate::config::{parse_config, parse_numbers};
+use crate::enemy::{Enemy, EnemyKind, DEFAULT_CAT_SPEED};
+use crate::generator::EndlessRun;
+use crate::objectives::{FailCondition, Objective, Objectives};
+use crate::validate::validate;
+
+// Level files in the order they appear on the start screen
+pub const LEVEL_FILES: [&str; 2] = ["levels/level_a.txt", "levels/level_b.txt"];
+
+// Separates the `key = value` header of a level file from its ASCII maze
+const GRID_SEPARATOR: &str = "---";
+
+const DEFAULT_SKY_COLOR: u32 = 0xFF8ECAE6;
+
+// A level file as written, before any of its assets are loaded
+pub struct LevelFile {
+    pub path: String,
+    pub grid_line: usize, // Line of the file holding the first maze row, counting from 1
+    pub name: String,
     pub maze: Vec<Vec<char>>,
-    pub player_start_pos: (f32, f32),
-    pub cat_img: Vec<u32>,
-    pub cat_width: usize,
-    pub cat_height: usize,
-    pub bunnies_to_collect: Option<usize>,
-    pub bunny_img: Option<Vec<u32>>,
-    pub bunny_width: Option<usize>,
-    pub bunny_height: Option<usize>,
+    pub spawn: (f32, f32, f32), // x, y and angle in radians
+    pub cats: Vec<Enemy>,
+    pub pickup_positions: Vec<na::Point3<f32>>,
+    pub enemy_texture: String,
+    pub cheshire_texture: Option<String>, // Falls back to the enemy texture
+    pub pickup_texture: Option<String>,
+    pub wall_texture: String,
+    pub sky_color: u32,
+    pub music: Option<String>,
+    pub objectives: Objectives,
 }
 
-impl Level {
-    pub fn load(level: usize) -> Result<Level, Box<dyn Error>> {
-        match level {
-            0 => {
-                let (maze, player_position) = load_maze("maze.txt")?;
-                let (cat_img, cat_width, cat_height) = load_and_resize_image("image/card.jpeg", 100, 100)?;
-                Ok(Level {
-                    maze,
-                    player_start_pos: (player_position.1 as f32, player_position.0 as f32),
-                    cat_img,
-                    cat_width,
-                    cat_height,
-                    bunnies_to_collect: None,
-                    bunny_img: None,
-                    bunny_width: None,
-                    bunny_height: None,
-                })
+impl LevelFile {
+    // Reads a level file and refuses it if validation finds any problem
+    pub fn read(path: &str) -> Result<LevelFile, Box<dyn Error>> {
+        LevelFile::parse(path)?.checked()
+    }
+
+    // Parses a level file without validating it
+    pub fn parse(path: &str) -> Result<LevelFile, Box<dyn Error>> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        LevelFile::parse_contents(path, &contents)
+    }
+
+    // Parses the text of a level file that was read from `path`: a `key = value`
+    // header, a `---` line, then the maze where every character is one cell.
+    // Spawn, cat and bunny glyphs in the maze add to what the header declares.
+    pub fn parse_contents(path: &str, contents: &str) -> Result<LevelFile, Box<dyn Error>> {
+        let (header, grid, grid_line) = split_header(contents)
+            .ok_or_else(|| format!("{}: missing '{}' line before the maze", path, GRID_SEPARATOR))?;
+
+        let mut maze: Vec<Vec<char>> = grid.lines().map(|line| line.chars().collect()).collect();
+        while maze.last().is_some_and(|row| row.is_empty()) {
+            maze.pop(); // Blank lines at the end of the file are not part of the maze
+        }
+        if maze.is_empty() {
+            return Err(format!("{}: the maze is empty", path).into());
+        }
+
+        let mut level = LevelFile {
+            path: path.to_string(),
+            grid_line,
+            name: String::new(),
+            maze: Vec::new(),
+            spawn: (1.5, 1.5, 0.0),
+            cats: Vec::new(),
+            pickup_positions: Vec::new(),
+            enemy_texture: String::new(),
+            cheshire_texture: None,
+            pickup_texture: None,
+            wall_texture: String::new(),
+            sky_color: DEFAULT_SKY_COLOR,
+            music: None,
+            objectives: Objectives { win: Vec::new(), fail: Vec::new() },
+        };
+
+        let mut cat_speed = DEFAULT_CAT_SPEED;
+        for line in parse_config(path, header)? {
+            let invalid = || format!("{}:{}: invalid value '{}' for {}", path, line.line_no, line.value, line.key);
+            match line.key.as_str() {
+                "name" => level.name = line.value.clone(),
+                "spawn" => {
+                    let values = parse_numbers(&line.value).ok_or_else(invalid)?;
+                    level.spawn = match values[..] {
+                        [x, y] => (x, y, 0.0),
+                        [x, y, angle] => (x, y, angle.to_radians()),
+                        _ => return Err(invalid().into()),
+                    };
+                }
+                "goal" => {
+                    let (x, y) = parse_cell(&line.value).ok_or_else(invalid)?;
+                    let cell = maze.get_mut(y).and_then(|row| row.get_mut(x)).ok_or_else(invalid)?;
+                    *cell = 'g';
+                }
+                "checkpoint" => {
+                    let (x, y) = parse_cell(&line.value).ok_or_else(invalid)?;
+                    let cell = maze.get_mut(y).and_then(|row| row.get_mut(x)).ok_or_else(invalid)?;
+                    *cell = 'k';
+                }
+                "cat" | "cheshire" => {
+                    // The cat's position, then any patrol waypoints, separated by `;`
+                    let points: Option<Vec<na::Point3<f32>>> = line.value.split(';').map(parse_position).collect();
+                    let mut points = points.ok_or_else(invalid)?;
+                    let position = points.remove(0);
+                    let kind = if line.key == "cheshire" { EnemyKind::Cheshire } else { EnemyKind::Cat };
+                    level.cats.push(Enemy::new(kind, position, points, DEFAULT_CAT_SPEED));
+                }
+                "cat_speed" => cat_speed = line.value.parse().map_err(|_| invalid())?,
+                "pickup" => level.pickup_positions.push(parse_position(&line.value).ok_or_else(invalid)?),
+                "enemy_texture" => level.enemy_texture = line.value.clone(),
+                "cheshire_texture" => level.cheshire_texture = Some(line.value.clone()),
+                "pickup_texture" => level.pickup_texture = Some(line.value.clone()),
+                "wall_texture" => level.wall_texture = line.value.clone(),
+                "sky" => level.sky_color = parse_color(&line.value).ok_or_else(invalid)?,
+                "music" => level.music = Some(line.value.clone()),
+                "objective" => level.objectives.win.push(Objective::parse(&line.value).ok_or_else(invalid)?),
+                "fail" => level.objectives.fail.push(FailCondition::parse(&line.value).ok_or_else(invalid)?),
+                _ => return Err(format!("{}:{}: unknown level key '{}'", path, line.line_no, line.key).into()),
             }
-            1 => {
-                let (maze, player_position) = load_maze("maze2.txt")?;
-                let (bunny_img, bunny_width, bunny_height) = load_and_resize_image("image/bunny.jpeg", 100, 100)?;
-                let bunnies_to_collect = maze.iter().flat_map(|row| row.iter()).filter(|&&c| c == 'b').count();
-                Ok(Level {
-                    maze,
-                    player_start_pos: (player_position.1 as f32, player_position.0 as f32),
-                    cat_img: Vec::new(),
-                    cat_width: 0,
-                    cat_height: 0,
-                    bunnies_to_collect: Some(bunnies_to_collect),
-                    bunny_img: Some(bunny_img),
-                    bunny_width: Some(bunny_width),
-                    bunny_height: Some(bunny_height),
-                })
+        }
+
+        level.read_glyphs(&mut maze)?;
+        for cat in &mut level.cats {
+            cat.speed = cat_speed;
+        }
+
+        // Conditions the level leaves out keep the original game's rules
+        let defaults = Objectives::default();
+        if level.objectives.win.is_empty() {
+            level.objectives.win = defaults.win;
+        }
+        if level.objectives.fail.is_empty() {
+            level.objectives.fail = defaults.fail;
+        }
+
+        for (key, value) in [("name", &level.name), ("enemy_texture", &level.enemy_texture), ("wall_texture", &level.wall_texture)] {
+            if value.is_empty() {
+                return Err(format!("{}: missing '{}'", path, key).into());
             }
-            _ => Err("Invalid level".into()),
         }
+        if !level.pickup_positions.is_empty() && level.pickup_texture.is_none() {
+            return Err(format!("{}: pickups need a 'pickup_texture'", path).into());
+        }
+
+        level.maze = maze;
+        Ok(level)
+    }
+
+    // Turns spawn, cat and bunny glyphs into entities, leaving floor behind.
+    // Entities stand in the centre of their cell.
+    fn read_glyphs(&mut self, maze: &mut [Vec<char>]) -> Result<(), Box<dyn Error>> {
+        let mut spawn_found = false;
+        for (y, row) in maze.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let position = na::Point3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+                match *cell {
+                    'p' | '^' | '>' | 'v' | '<' => {
+                        if spawn_found {
+                            return Err(format!("{}:{}:{}: more than one spawn glyph", self.path, self.grid_line + y, x + 1).into());
+                        }
+                        spawn_found = true;
+                        let angle = match *cell {
+                            '>' => 0.0,
+                            'v' => PI / 2.0, // The maze's y axis points down the screen
+                            '<' => PI,
+                            '^' => -PI / 2.0,
+                            _ => self.spawn.2, // 'p' keeps the header's facing
+                        };
+                        self.spawn = (position.x, position.y, angle);
+                    }
+                    'c' => self.cats.push(Enemy::new(EnemyKind::Cat, position, Vec::new(), DEFAULT_CAT_SPEED)),
+                    'b' => self.pickup_positions.push(position),
+                    _ => continue,
+                }
+                *cell = ' ';
+            }
+        }
+        Ok(())
+    }
+
+    // Refuses the level if validation finds any problem
+    pub fn checked(self) -> Result<LevelFile, Box<dyn Error>> {
+        // Nothing plays the music yet, so a missing track is only worth a warning
+        if let Some(music) = &self.music {
+            if !Path::new(music).exists() {
+                eprintln!("warning: {}: music file '{}' not found", self.path, music);
+            }
+        }
+
+        let diagnostics = validate(&self);
+        if !diagnostics.is_empty() {
+            let messages: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+            return Err(messages.join("\n").into());
+        }
+        Ok(self)
     }
 }
 
-fn load_maze(filename: &str) -> Result<(Vec<Vec<char>>, (usize, usize)), Box<dyn Error>> {
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
-    
-    let maze: Vec<Vec<char>> = reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
-
-    // No need to find the player's position in the maze file
-    Ok((maze, (1, 1))) // Just return a dummy player position since it's manually set
+// Everything a level needs to start playing, with its images loaded
+pub struct Level {
+    pub name: String,
+    pub maze: Vec<Vec<char>>,
+    pub player_start_pos: (f32, f32), // x, y
+    pub player_start_angle: f32,
+    pub cats: Vec<Enemy>,
+    pub pickup_positions: Vec<na::Point3<f32>>,
+    pub cat_img: Image,
+    pub cheshire_img: Option<Image>,
+    pub pickup_img: Option<Image>,
+    pub wall_texture: Image,
+    pub sky_color: u32,
+    pub objectives: Objectives,
 }
 
-fn load_and_resize_image(path: &str, new_width: usize, new_height: usize) -> Result<(Vec<u32>, usize, usize), Box<dyn Error>> {
-    let img = image::open(path)?;
-    let img = img.resize(new_width as u32, new_height as u32, image::imageops::FilterType::Nearest);
-    let img = img.to_rgba8();
-    let pixels = img
-        .pixels()
-        .map(|p| {
-            let rgba = p.0;
-            ((rgba[0] as u32) << 16) | ((rgba[1] as u32) << 8) | (rgba[2] as u32) | ((rgba[3] as u32) << 24)
+impl Level {
+    pub fn load(level: usize) -> Result<Level, Box<dyn Error>> {
+        let path = LEVEL_FILES.get(level).ok_or_else(|| format!("invalid level {}", level))?;
+        Level::from_file(LevelFile::read(path)?)
+    }
+
+    // The next maze of an endless run
+    pub fn generate(run: &EndlessRun) -> Result<Level, Box<dyn Error>> {
+        let path = format!("endless round {}", run.round);
+        Level::from_file(LevelFile::parse_contents(&path, &run.level_file())?.checked()?)
+    }
+
+    fn from_file(file: LevelFile) -> Result<Level, Box<dyn Error>> {
+        let pickup_img = match &file.pickup_texture {
+            Some(texture) => Some(Image::load(texture, 100, 100)?),
+            None => None,
+        };
+        let cheshire_img = match &file.cheshire_texture {
+            Some(texture) => Some(Image::load(texture, 100, 100)?),
+            None => None,
+        };
+        let (x, y, angle) = file.spawn;
+        Ok(Level {
+            name: file.name,
+            maze: file.maze,
+            player_start_pos: (x, y),
+            player_start_angle: angle,
+            cats: file.cats,
+            pickup_positions: file.pickup_positions,
+            cat_img: Image::load(&file.enemy_texture, 100, 100)?,
+            cheshire_img,
+            pickup_img,
+            wall_texture: Image::load(&file.wall_texture, 100, 200)?,
+            sky_color: file.sky_color,
+            objectives: file.objectives,
         })
-        .collect();
-    Ok((pixels, new_width, new_height))
+    }
+}
+
+// Splits a level file at its separator line, keeping the header's line numbers
+// intact. Also returns the line number the maze starts on.
+fn split_header(contents: &str) -> Option<(&str, &str, usize)> {
+    let mut offset = 0;
+    for (index, line) in contents.split_inclusive('\n').enumerate() {
+        if line.trim() == GRID_SEPARATOR {
+            return Some((&contents[..offset], &contents[offset + line.len()..], index + 2));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn parse_position(value: &str) -> Option<na::Point3<f32>> {
+    match parse_numbers(value)?[..] {
+        [x, y] => Some(na::Point3::new(x, y, 0.0)),
+        _ => None,
+    }
+}
+
+fn parse_cell(value: &str) -> Option<(usize, usize)> {
+    let (x, y) = value.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+// Colors are written like the rest of the code, as 0xAARRGGBB or 0xRRGGBB
+fn parse_color(value: &str) -> Option<u32> {
+    let hex = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X"))?;
+    let color = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(0xFF000000 | color),
+        8 => Some(color),
+        _ => None,
+    }
 }
@@ -0,0 +1,160 @@
+use crate::game::{Outcome, World};
+
+// Something the player has to do to win. A level is won on the tick where
+// every one of its objectives holds at the same time.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Objective {
+    ReachGoal,                // Stand on any 'g' cell
+    ReachCell(usize, usize),  // Stand on the cell at x, y
+    Collect(Option<usize>),   // Collect this many pickups, or all of them for `None`
+    Survive(f32),             // Stay in the level for this many seconds
+}
+
+// Something that loses the level as soon as it happens
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FailCondition {
+    Caught,         // Cats take the player's last life
+    TimeLimit(f32), // The level is not won within this many seconds
+    Detected,       // A cat spots the player and gives chase
+}
+
+impl Objective {
+    // Parses the value of an `objective = ...` level line
+    pub fn parse(value: &str) -> Option<Objective> {
+        let (kind, argument) = split_kind(value);
+        match (kind, argument) {
+            ("reach_goal", "") => Some(Objective::ReachGoal),
+            ("reach", cell) => {
+                let (x, y) = cell.split_once(',')?;
+                Some(Objective::ReachCell(x.trim().parse().ok()?, y.trim().parse().ok()?))
+            }
+            ("collect", "all") => Some(Objective::Collect(None)),
+            ("collect", count) => Some(Objective::Collect(Some(count.parse().ok()?))),
+            ("survive", seconds) => Some(Objective::Survive(seconds.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    pub fn is_met(&self, world: &World) -> bool {
+        let (x, y) = (world.player.x as usize, world.player.y as usize);
+        match *self {
+            Objective::ReachGoal => world.maze.get(y).and_then(|row| row.get(x)) == Some(&'g'),
+            Objective::ReachCell(cell_x, cell_y) => (x, y) == (cell_x, cell_y),
+            Objective::Collect(count) => world.pickups_collected >= count.unwrap_or(world.pickups_total),
+            Objective::Survive(seconds) => world.elapsed >= seconds,
+        }
+    }
+
+    // Reaching a place only counts at the moment the player is there, while
+    // everything else stays done once it is done
+    fn is_destination(&self) -> bool {
+        matches!(self, Objective::ReachGoal | Objective::ReachCell(..))
+    }
+
+    // HUD line showing progress towards the objective
+    pub fn status(&self, world: &World) -> String {
+        match *self {
+            Objective::ReachGoal => "REACH THE GOAL".to_string(),
+            Objective::ReachCell(x, y) => format!("REACH {}, {}", x, y),
+            Objective::Collect(count) => {
+                format!("COLLECTED {}/{}", world.pickups_collected, count.unwrap_or(world.pickups_total))
+            }
+            Objective::Survive(seconds) => format!("SURVIVE {}", (seconds - world.elapsed).max(0.0).ceil()),
+        }
+    }
+}
+
+impl FailCondition {
+    // Parses the value of a `fail = ...` level line
+    pub fn parse(value: &str) -> Option<FailCondition> {
+        match split_kind(value) {
+            ("caught", "") => Some(FailCondition::Caught),
+            ("time_limit", seconds) => Some(FailCondition::TimeLimit(seconds.parse().ok()?)),
+            ("detected", "") => Some(FailCondition::Detected),
+            _ => None,
+        }
+    }
+
+    pub fn has_happened(&self, world: &World) -> bool {
+        match *self {
+            FailCondition::Caught => world.health.is_out_of_lives(),
+            FailCondition::TimeLimit(seconds) => world.elapsed >= seconds,
+            FailCondition::Detected => world.cats.iter().any(|cat| cat.has_spotted_player()),
+        }
+    }
+
+    // HUD line for conditions worth watching, such as a running clock
+    pub fn status(&self, world: &World) -> Option<String> {
+        match *self {
+            FailCondition::TimeLimit(seconds) => Some(format!("TIME LEFT {}", (seconds - world.elapsed).max(0.0).ceil())),
+            FailCondition::Caught | FailCondition::Detected => None,
+        }
+    }
+}
+
+// The win and fail conditions a level declares
+#[derive(Clone, PartialEq, Debug)]
+pub struct Objectives {
+    pub win: Vec<Objective>,
+    pub fail: Vec<FailCondition>,
+}
+
+impl Default for Objectives {
+    // Levels that declare nothing play like the original game
+    fn default() -> Self {
+        Objectives {
+            win: vec![Objective::ReachGoal],
+            fail: vec![FailCondition::Caught],
+        }
+    }
+}
+
+impl Objectives {
+    // Checks the conditions after a tick. Failing wins over succeeding on the same tick.
+    // Losing the last life always fails, even in levels that do not list `caught`,
+    // since the player could not be hurt any more.
+    pub fn evaluate(&self, world: &World) -> Option<Outcome> {
+        if world.health.is_out_of_lives() || self.fail.iter().any(|condition| condition.has_happened(world)) {
+            return Some(Outcome::Fail);
+        }
+        if self.win.iter().all(|objective| objective.is_met(world)) {
+            return Some(Outcome::Success);
+        }
+        None
+    }
+
+    // Destinations stay locked until every other objective is done
+    pub fn destinations_unlocked(&self, world: &World) -> bool {
+        self.win
+            .iter()
+            .filter(|objective| !objective.is_destination())
+            .all(|objective| objective.is_met(world))
+    }
+
+    // HUD lines with whether each one is already done
+    pub fn status(&self, world: &World) -> Vec<(String, bool)> {
+        let unlocked = self.destinations_unlocked(world);
+        let mut lines: Vec<(String, bool)> = self
+            .win
+            .iter()
+            .map(|objective| {
+                if objective.is_destination() {
+                    let status = objective.status(world);
+                    (if unlocked { status } else { format!("{} (LOCKED)", status) }, false)
+                } else {
+                    (objective.status(world), objective.is_met(world))
+                }
+            })
+            .collect();
+        lines.extend(self.fail.iter().filter_map(|condition| condition.status(world)).map(|line| (line, false)));
+        lines
+    }
+}
+
+// Splits `kind argument` values such as `collect 3` or `reach 4, 7`
+fn split_kind(value: &str) -> (&str, &str) {
+    match value.trim().split_once(char::is_whitespace) {
+        Some((kind, argument)) => (kind, argument.trim()),
+        None => (value.trim(), ""),
+    }
+}
@@ -0,0 +1,208 @@
+// A* over the maze grid, where every character is one cell and walls are
+// `+ - |`. Paths move between the four neighbouring cells.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::collision::is_wall_cell;
+
+pub type Cell = (usize, usize); // x, y
+
+// Finds a shortest path from `start` to `goal`, both included. Returns None if
+// either end is a wall or the goal cannot be reached.
+pub fn find_path(maze: &[Vec<char>], start: Cell, goal: Cell) -> Option<Vec<Cell>> {
+    if !is_open(maze, start) || !is_open(maze, goal) {
+        return None;
+    }
+
+    // Ties on cost go to the most recently pushed cell, which keeps paths straight
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Cell, Cell> = HashMap::new();
+    let mut cost: HashMap<Cell, usize> = HashMap::new();
+    let mut pushed = 0usize;
+    open.push((Reverse(distance(start, goal)), pushed, start));
+    cost.insert(start, 0);
+
+    while let Some((_, _, cell)) = open.pop() {
+        if cell == goal {
+            let mut path = vec![cell];
+            let mut current = cell;
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let next_cost = cost[&cell] + 1;
+        for neighbour in neighbours(maze, cell) {
+            if cost.get(&neighbour).is_none_or(|&known| next_cost < known) {
+                cost.insert(neighbour, next_cost);
+                came_from.insert(neighbour, cell);
+                pushed += 1;
+                open.push((Reverse(next_cost + distance(neighbour, goal)), pushed, neighbour));
+            }
+        }
+    }
+    None
+}
+
+// Keeps the last planned path and only plans again once the target moves to
+// another cell or the follower strays off the path
+#[derive(Clone, Default)]
+pub struct PathCache {
+    target: Option<Cell>,
+    path: Vec<Cell>,
+}
+
+impl PathCache {
+    // The next cell to walk to on the way from `from` to `target`, or None if
+    // there is no way there. Returns `target` itself once `from` is on it.
+    pub fn next_cell(&mut self, maze: &[Vec<char>], from: Cell, target: Cell) -> Option<Cell> {
+        // Drop the cells already walked past
+        if let Some(index) = self.path.iter().position(|&cell| cell == from) {
+            self.path.drain(..index);
+        }
+
+        let stale = self.target != Some(target) || self.path.first() != Some(&from);
+        if stale {
+            self.path = find_path(maze, from, target).unwrap_or_default();
+            self.target = Some(target);
+        }
+        match self.path.len() {
+            0 => None,
+            1 => Some(self.path[0]),
+            _ => Some(self.path[1]),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.target = None;
+        self.path.clear();
+    }
+}
+
+fn is_open(maze: &[Vec<char>], (x, y): Cell) -> bool {
+    !is_wall_cell(maze, x as i32, y as i32)
+}
+
+fn distance(a: Cell, b: Cell) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+fn neighbours(maze: &[Vec<char>], (x, y): Cell) -> impl Iterator<Item = Cell> + '_ {
+    let candidates = [
+        x.checked_sub(1).map(|x| (x, y)),
+        Some((x + 1, y)),
+        y.checked_sub(1).map(|y| (x, y)),
+        Some((x, y + 1)),
+    ];
+    candidates.into_iter().flatten().filter(move |&cell| is_open(maze, cell))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{LevelFile, LEVEL_FILES};
+
+    fn goal(maze: &[Vec<char>]) -> Cell {
+        maze.iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|&cell| cell == 'g').map(|x| (x, y)))
+            .expect("bundled mazes have a goal")
+    }
+
+    fn spawn(level: &LevelFile) -> Cell {
+        (level.spawn.0 as usize, level.spawn.1 as usize)
+    }
+
+    // Shortest path length by breadth-first search, to check A* against
+    fn bfs_length(maze: &[Vec<char>], start: Cell, goal: Cell) -> Option<usize> {
+        let mut seen = vec![start];
+        let mut frontier = vec![start];
+        let mut steps = 0;
+        while !frontier.is_empty() {
+            if frontier.contains(&goal) {
+                return Some(steps);
+            }
+            let mut next = Vec::new();
+            for &cell in &frontier {
+                for neighbour in neighbours(maze, cell) {
+                    if !seen.contains(&neighbour) {
+                        seen.push(neighbour);
+                        next.push(neighbour);
+                    }
+                }
+            }
+            frontier = next;
+            steps += 1;
+        }
+        None
+    }
+
+    #[test]
+    fn finds_shortest_path_from_spawn_to_goal_in_bundled_levels() {
+        for path in LEVEL_FILES {
+            let level = LevelFile::parse(path).unwrap();
+            let (start, goal) = (spawn(&level), goal(&level.maze));
+            let found = find_path(&level.maze, start, goal).unwrap_or_else(|| panic!("{}: no path", path));
+
+            assert_eq!(found.first(), Some(&start));
+            assert_eq!(found.last(), Some(&goal));
+            assert_eq!(Some(found.len() - 1), bfs_length(&level.maze, start, goal), "{}: path is not the shortest", path);
+            for step in found.windows(2) {
+                assert_eq!(distance(step[0], step[1]), 1, "{}: path skips a cell", path);
+            }
+            assert!(found.iter().all(|&cell| is_open(&level.maze, cell)), "{}: path goes through a wall", path);
+        }
+    }
+
+    #[test]
+    fn path_to_own_cell_is_that_cell() {
+        let level = LevelFile::parse(LEVEL_FILES[0]).unwrap();
+        let start = spawn(&level);
+        assert_eq!(find_path(&level.maze, start, start), Some(vec![start]));
+    }
+
+    #[test]
+    fn walls_and_cells_outside_the_maze_have_no_path() {
+        let level = LevelFile::parse(LEVEL_FILES[0]).unwrap();
+        let start = spawn(&level);
+        assert_eq!(find_path(&level.maze, start, (0, 0)), None);
+        assert_eq!(find_path(&level.maze, start, (100, 100)), None);
+    }
+
+    #[test]
+    fn walled_off_goal_has_no_path() {
+        let mut maze = LevelFile::parse(LEVEL_FILES[0]).unwrap().maze;
+        let goal = goal(&maze);
+        for (x, y) in neighbours(&maze.clone(), goal).collect::<Vec<_>>() {
+            maze[y][x] = '+';
+        }
+        assert_eq!(find_path(&maze, (1, 1), goal), None);
+    }
+
+    #[test]
+    fn cache_follows_the_path_and_replans_when_the_target_moves() {
+        let level = LevelFile::parse(LEVEL_FILES[1]).unwrap();
+        let maze = &level.maze;
+        let (start, goal) = (spawn(&level), goal(maze));
+        let mut cache = PathCache::default();
+
+        // Walking the cached path reaches the goal in the shortest number of steps
+        let mut cell = start;
+        let mut steps = 0;
+        while cell != goal {
+            cell = cache.next_cell(maze, cell, goal).unwrap();
+            steps += 1;
+        }
+        assert_eq!(Some(steps), bfs_length(maze, start, goal));
+        assert_eq!(cache.next_cell(maze, goal, goal), Some(goal));
+
+        // A new target cell leads back towards it
+        let next = cache.next_cell(maze, goal, start).unwrap();
+        assert_eq!(distance(next, goal), 1);
+        assert!(bfs_length(maze, next, start) < bfs_length(maze, goal, start));
+    }
+}
@@ -0,0 +1,133 @@
+// How enemies notice the player, and the alert state that follows from it
+
+use std::f32::consts::PI;
+
+use crate::player::Player;
+use crate::raycaster::cast_ray;
+
+const VIEW_DISTANCE: f32 = 5.0;
+const VIEW_HALF_ANGLE: f32 = PI / 4.0; // The view cone spans 90 degrees
+const CLOSE_DISTANCE: f32 = 1.0; // Seen this close, the player is recognised at once
+const HEARING_RADIUS: f32 = 3.0; // Sprinting can be heard through walls within this distance
+
+const NOTICE_TIME: f32 = 0.6; // Seconds of noticing the player before reacting
+const SUSPICIOUS_TIME: f32 = 2.0; // Seconds without a stimulus before shrugging it off
+const LOSE_TRACK_TIME: f32 = 0.5; // Seconds out of sight before a chase turns into a search
+const SEARCH_TIME: f32 = 4.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlertState {
+    Idle,       // Going about the patrol
+    Suspicious, // Noticed something and stopped to look
+    Chase,      // Knows where the player is
+    Search,     // Lost the player and is looking around where they were last seen
+    Return,     // Gave up and is heading back to the patrol
+}
+
+// Whether the player is inside the view cone, in range and not behind a wall
+pub fn can_see(maze: &[Vec<char>], x: f32, y: f32, facing: f32, player: &Player) -> bool {
+    let (dx, dy) = (player.x - x, player.y - y);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance > VIEW_DISTANCE {
+        return false;
+    }
+
+    let angle_to_player = dy.atan2(dx);
+    let off_centre = (angle_to_player - facing + PI).rem_euclid(2.0 * PI) - PI;
+    off_centre.abs() <= VIEW_HALF_ANGLE && cast_ray(maze, x, y, angle_to_player).distance >= distance
+}
+
+// Only sprinting makes enough noise to be heard
+pub fn can_hear(x: f32, y: f32, player: &Player) -> bool {
+    let (dx, dy) = (player.x - x, player.y - y);
+    player.is_sprinting && dx * dx + dy * dy <= HEARING_RADIUS * HEARING_RADIUS
+}
+
+#[derive(Clone)]
+pub struct Perception {
+    pub state: AlertState,
+    pub last_known: Option<na::Point2<f32>>, // Where the player was last seen or heard
+    suspicion: f32,  // Builds up while the player is noticed, from 0 to NOTICE_TIME
+    state_time: f32, // Seconds since the last stimulus or state change
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Perception {
+            state: AlertState::Idle,
+            last_known: None,
+            suspicion: 0.0,
+            state_time: 0.0,
+        }
+    }
+}
+
+impl Perception {
+    // Advances the state machine by `dt` seconds given what the enemy sensed this tick
+    pub fn update(&mut self, sees: bool, hears: bool, distance: f32, player: &Player, dt: f32) {
+        let noticed = sees || hears;
+        if noticed {
+            self.last_known = Some(na::Point2::new(player.x, player.y));
+        }
+        self.state_time += dt;
+
+        match self.state {
+            AlertState::Idle | AlertState::Return => {
+                if noticed {
+                    self.enter(AlertState::Suspicious);
+                }
+            }
+            AlertState::Suspicious => {
+                if noticed {
+                    self.suspicion += dt;
+                    self.state_time = 0.0;
+                }
+                if sees && (self.suspicion >= NOTICE_TIME || distance <= CLOSE_DISTANCE) {
+                    self.enter(AlertState::Chase);
+                } else if hears && self.suspicion >= NOTICE_TIME {
+                    self.enter(AlertState::Search); // Go and look where the noise came from
+                } else if self.state_time >= SUSPICIOUS_TIME {
+                    self.enter(AlertState::Return);
+                }
+            }
+            AlertState::Chase => {
+                if noticed {
+                    self.state_time = 0.0;
+                } else if self.state_time >= LOSE_TRACK_TIME {
+                    self.enter(AlertState::Search);
+                }
+            }
+            AlertState::Search => {
+                if sees {
+                    self.enter(AlertState::Chase);
+                } else if self.state_time >= SEARCH_TIME {
+                    self.enter(AlertState::Return);
+                }
+            }
+        }
+    }
+
+    // Called by the enemy once it is back on its patrol
+    pub fn back_on_patrol(&mut self) {
+        if self.state == AlertState::Return {
+            self.enter(AlertState::Idle);
+            self.last_known = None;
+        }
+    }
+
+    // Forgets the player, as after the player respawns at a checkpoint
+    pub fn forget_player(&mut self) {
+        if self.state != AlertState::Idle {
+            self.enter(AlertState::Return);
+        }
+        self.last_known = None;
+    }
+
+    fn enter(&mut self, state: AlertState) {
+        self.state = state;
+        self.state_time = 0.0;
+        if state == AlertState::Suspicious {
+            self.suspicion = 0.0;
+        }
+    }
+}
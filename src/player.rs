@@ -1,34 +1,107 @@
+use crate::collision::{move_and_slide, PLAYER_RADIUS};
+
+// Tuning for how the player moves; speeds are per second so they do not depend
+// on the frame rate
+pub struct Movement {
+    pub walk_speed: f32,        // Cells per second
+    pub sprint_multiplier: f32,
+    pub turn_speed: f32,        // Radians per second
+    pub inertia: bool,          // Use acceleration and friction instead of instant speed changes
+    pub acceleration: f32,      // How fast the velocity reaches the target, per second
+    pub friction: f32,          // How fast the velocity decays without input, per second
+    pub max_stamina: f32,       // Seconds of sprinting from a full bar
+    pub stamina_regen: f32,     // Stamina recovered per second while not sprinting
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Movement {
+            walk_speed: 3.0,
+            sprint_multiplier: 1.8,
+            turn_speed: 1.5,
+            inertia: false,
+            acceleration: 12.0,
+            friction: 8.0,
+            max_stamina: 2.0,
+            stamina_regen: 0.5,
+        }
+    }
+}
+
+// What the player wants to do during one tick, sampled from the input
+#[derive(Clone, Copy, Default)]
+pub struct PlayerCommand {
+    pub forward: f32, // -1 (backward) to 1 (forward)
+    pub strafe: f32,  // -1 (left) to 1 (right)
+    pub turn: f32,    // -1 (left) to 1 (right)
+    pub sprint: bool,
+}
+
+#[derive(Clone)]
 pub struct Player {
     pub x: f32,
     pub y: f32,
-    pub z: f32,
     pub angle: f32,
     pub fov: f32,
+    pub velocity: (f32, f32),
+    pub stamina: f32,
+    pub is_sprinting: bool,
 }
 
 impl Player {
-    pub fn new(x: f32, y: f32, z:f32, fov: f32) -> Self {
-        Self { x, y, z, angle: 0.0, fov }
+    pub fn new(x: f32, y: f32, fov: f32) -> Self {
+        Self { x, y, angle: 0.0, fov, velocity: (0.0, 0.0), stamina: Movement::default().max_stamina, is_sprinting: false }
     }
 
-    pub fn move_forward(&mut self, distance: f32, maze: &[Vec<char>]) {
-        let new_x = self.x + distance * self.angle.cos();
-        let new_y = self.y + distance * self.angle.sin();
+    // `forward` and `strafe` go from -1 to 1 (backward/left to forward/right)
+    pub fn walk(&mut self, forward: f32, strafe: f32, sprint: bool, dt: f32, maze: &[Vec<char>], movement: &Movement) {
+        let (sin, cos) = self.angle.sin_cos();
+        let mut dir_x = forward * cos - strafe * sin;
+        let mut dir_y = forward * sin + strafe * cos;
 
-        if !self.check_collision(new_x, new_y, maze) {
-            self.x = new_x;
-            self.y = new_y;
+        // Walking diagonally should not be faster than walking straight
+        let length = (dir_x * dir_x + dir_y * dir_y).sqrt();
+        if length > 1.0 {
+            dir_x /= length;
+            dir_y /= length;
         }
-    }
 
-    pub fn move_backward(&mut self, distance: f32, maze: &[Vec<char>]) {
-        let new_x = self.x - distance * self.angle.cos();
-        let new_y = self.y - distance * self.angle.sin();
+        let moving = length > 0.0;
+        self.is_sprinting = sprint && moving && self.stamina > 0.0;
+        if self.is_sprinting {
+            self.stamina = (self.stamina - dt).max(0.0);
+        } else {
+            self.stamina = (self.stamina + movement.stamina_regen * dt).min(movement.max_stamina);
+        }
+
+        let speed = movement.walk_speed * if self.is_sprinting { movement.sprint_multiplier } else { 1.0 };
+        let target = (dir_x * speed, dir_y * speed);
+
+        self.velocity = if !movement.inertia {
+            target
+        } else if moving {
+            let blend = (movement.acceleration * dt).min(1.0);
+            (
+                self.velocity.0 + (target.0 - self.velocity.0) * blend,
+                self.velocity.1 + (target.1 - self.velocity.1) * blend,
+            )
+        } else {
+            let keep = (1.0 - movement.friction * dt).max(0.0);
+            (self.velocity.0 * keep, self.velocity.1 * keep)
+        };
+
+        let (dx, dy) = (self.velocity.0 * dt, self.velocity.1 * dt);
+        let (new_x, new_y) = move_and_slide(maze, self.x, self.y, dx, dy, PLAYER_RADIUS);
 
-        if !self.check_collision(new_x, new_y, maze) {
-            self.x = new_x;
-            self.y = new_y;
+        // Drop the velocity along any axis a wall blocked
+        if (new_x - self.x - dx).abs() > 1e-4 {
+            self.velocity.0 = 0.0;
         }
+        if (new_y - self.y - dy).abs() > 1e-4 {
+            self.velocity.1 = 0.0;
+        }
+        self.x = new_x;
+        self.y = new_y;
     }
 
     pub fn turn_left(&mut self, angle: f32) {
@@ -38,18 +111,4 @@ impl Player {
     pub fn turn_right(&mut self, angle: f32) {
         self.angle += angle;
     }
-
-    fn check_collision(&self, x: f32, y: f32, maze: &[Vec<char>]) -> bool {
-        let maze_x = x as usize;
-        let maze_y = y as usize;
-
-        if maze_y >= maze.len() || maze_x >= maze[0].len() {
-            return true;
-        }
-
-        match maze[maze_y][maze_x] {
-            '+' | '-' | '|' => true, // These characters represent walls
-            _ => false,
-        }
-    }
-}
\ No newline at end of file
+}
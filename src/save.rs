@@ -0,0 +1,448 @@
+use std::error::Error;
+use std::fs;
+
+use crate::collision::is_wall_cell;
+use crate::config::{parse_numbers, read_config, ConfigLine};
+use crate::game::World;
+use crate::health::Difficulty;
+use crate::level::LEVEL_FILES;
+
+pub const SAVE_FILE: &str = "save.cfg";
+
+// Version 1 numbered levels by their place on the start screen. Version 2
+// names them by level file, so progress stays with the right level when levels
+// are added or reordered. Bump when the format changes, and upgrade older
+// lines in `migrate`.
+const SAVE_VERSION: u32 = 2;
+
+// A level as it was when saved from the pause menu
+#[derive(Clone)]
+pub struct SavedLevel {
+    pub level: usize, // Index into LEVEL_FILES
+    pub difficulty: Difficulty,
+    pub player: (f32, f32, f32), // x, y and angle
+    pub health: u32,
+    pub lives: u32,
+    pub hits_taken: u32,
+    pub elapsed: f32,
+    pub pickups_collected: usize,
+    pub pickups: Vec<na::Point3<f32>>, // Pickups still waiting to be collected
+    pub cats: Vec<(na::Point3<f32>, f32)>, // Position and facing, in level file order
+}
+
+impl SavedLevel {
+    pub fn capture(level: usize, difficulty: Difficulty, world: &World) -> Self {
+        SavedLevel {
+            level,
+            difficulty,
+            player: (world.player.x, world.player.y, world.player.angle),
+            health: world.health.health,
+            lives: world.health.lives,
+            hits_taken: world.health.hits_taken,
+            elapsed: world.elapsed,
+            pickups_collected: world.pickups_collected,
+            pickups: world.pickup_positions.clone(),
+            cats: world.cats.iter().map(|cat| (cat.position, cat.facing)).collect(),
+        }
+    }
+
+    // Puts the saved state into a world freshly loaded from the same level file.
+    // The file can be edited by hand, so anything saved outside the open floor
+    // is left where the level puts it, and counts are kept within the rules.
+    pub fn apply(&self, world: &mut World) -> Result<(), Box<dyn Error>> {
+        if self.cats.len() != world.cats.len() {
+            return Err(format!(
+                "{}: the save has {} cats but {} has {}",
+                SAVE_FILE,
+                self.cats.len(),
+                LEVEL_FILES[self.level],
+                world.cats.len()
+            )
+            .into());
+        }
+
+        let (x, y, angle) = self.player;
+        if is_open_floor(&world.maze, x, y) && angle.is_finite() {
+            world.place_player(x, y, angle);
+        } else {
+            eprintln!("warning: {}: the saved player at ({}, {}) is not on open floor; starting from the spawn", SAVE_FILE, x, y);
+        }
+        let rules = world.health.rules;
+        world.health.health = self.health.clamp(1, rules.max_health);
+        world.health.lives = self.lives.clamp(1, rules.lives);
+        world.health.hits_taken = self.hits_taken;
+        world.elapsed = self.elapsed.max(0.0);
+        world.pickups_collected = self.pickups_collected;
+        world.pickup_positions = self.pickups.iter().copied().filter(|pickup| is_open_floor(&world.maze, pickup.x, pickup.y)).collect();
+        for (cat, &(position, facing)) in world.cats.iter_mut().zip(&self.cats) {
+            if is_open_floor(&world.maze, position.x, position.y) && facing.is_finite() {
+                cat.place(position, facing);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Everything kept between sessions
+pub struct SaveFile {
+    pub unlocked: usize, // Levels are unlocked in order, starting with the first
+    pub best_times: Vec<Option<f32>>, // Fastest win in seconds, per level
+    pub level: Option<SavedLevel>,
+    pub read_only: bool, // Set when the file could not be loaded, so the progress in it is not written over
+}
+
+impl Default for SaveFile {
+    fn default() -> Self {
+        SaveFile {
+            unlocked: 1,
+            best_times: vec![None; LEVEL_FILES.len()],
+            level: None,
+            read_only: false,
+        }
+    }
+}
+
+impl SaveFile {
+    // A missing file is a new game
+    pub fn load(path: &str) -> Result<SaveFile, Box<dyn Error>> {
+        match read_config(path)? {
+            Some(lines) => SaveFile::from_lines(path, lines),
+            None => Ok(SaveFile::default()),
+        }
+    }
+
+    // Builds a save from the lines of a file read from `path`
+    pub fn from_lines(path: &str, lines: Vec<ConfigLine>) -> Result<SaveFile, Box<dyn Error>> {
+        // Files written by hand may leave out the `version` line
+        let version = match lines.iter().find(|line| line.key == "version") {
+            Some(line) => line
+                .value
+                .parse()
+                .map_err(|_| format!("{}:{}: invalid version '{}'", path, line.line_no, line.value))?,
+            None => 1,
+        };
+
+        // A bad line only loses what it describes, not the rest of the progress
+        let mut save = SaveFile::default();
+        let mut level: Option<SavedLevel> = None;
+        for line in migrate(path, lines, version)? {
+            if let Err(err) = save.read_line(path, &line, &mut level) {
+                eprintln!("warning: {}; line ignored", err);
+            }
+        }
+        save.level = level;
+        Ok(save)
+    }
+
+    // Reads one line into the save, or into the saved level started by the last `level` line
+    fn read_line(&mut self, path: &str, line: &ConfigLine, level: &mut Option<SavedLevel>) -> Result<(), String> {
+        let invalid = || format!("{}:{}: invalid value '{}' for {}", path, line.line_no, line.value, line.key);
+        let numbers = || parse_numbers(&line.value).ok_or_else(invalid);
+        match line.key.as_str() {
+            "version" => {}
+            "unlocked" => {
+                let unlocked: usize = line.value.parse().map_err(|_| invalid())?;
+                self.unlocked = unlocked.clamp(1, LEVEL_FILES.len());
+            }
+            "best_time" => {
+                let (file, seconds) = line.value.split_once(',').ok_or_else(invalid)?;
+                let index = level_index(file).ok_or_else(invalid)?;
+                self.best_times[index] = Some(seconds.trim().parse().map_err(|_| invalid())?);
+            }
+            "level" => {
+                *level = Some(SavedLevel {
+                    level: level_index(&line.value).ok_or_else(invalid)?,
+                    difficulty: Difficulty::Normal,
+                    player: (0.0, 0.0, 0.0),
+                    health: 0,
+                    lives: 0,
+                    hits_taken: 0,
+                    elapsed: 0.0,
+                    pickups_collected: 0,
+                    pickups: Vec::new(),
+                    cats: Vec::new(),
+                });
+            }
+            // Everything else describes the saved level, so it must come after `level`
+            key => {
+                let saved = level
+                    .as_mut()
+                    .ok_or_else(|| format!("{}:{}: '{}' before any 'level' line", path, line.line_no, key))?;
+                match key {
+                    "difficulty" => saved.difficulty = Difficulty::parse(&line.value).ok_or_else(invalid)?,
+                    "player" => match numbers()?[..] {
+                        [x, y, angle] => saved.player = (x, y, angle.to_radians()),
+                        _ => return Err(invalid()),
+                    },
+                    "health" => saved.health = line.value.parse().map_err(|_| invalid())?,
+                    "lives" => saved.lives = line.value.parse().map_err(|_| invalid())?,
+                    "hits" => saved.hits_taken = line.value.parse().map_err(|_| invalid())?,
+                    "elapsed" => saved.elapsed = line.value.parse().map_err(|_| invalid())?,
+                    "collected" => saved.pickups_collected = line.value.parse().map_err(|_| invalid())?,
+                    "pickup" => match numbers()?[..] {
+                        [x, y] => saved.pickups.push(na::Point3::new(x, y, 0.0)),
+                        _ => return Err(invalid()),
+                    },
+                    "cat" => match numbers()?[..] {
+                        [x, y, facing] => saved.cats.push((na::Point3::new(x, y, 0.0), facing.to_radians())),
+                        _ => return Err(invalid()),
+                    },
+                    _ => return Err(format!("{}:{}: unknown save entry '{}'", path, line.line_no, key)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        if self.read_only {
+            return Err(format!("{} could not be loaded, so it is left as it is", path).into());
+        }
+        fs::write(path, self.to_contents())?;
+        Ok(())
+    }
+
+    pub fn to_contents(&self) -> String {
+        let mut contents = format!("# Mundo3D save file\nversion = {}\nunlocked = {}\n", SAVE_VERSION, self.unlocked);
+        for (index, best_time) in self.best_times.iter().enumerate() {
+            if let Some(seconds) = best_time {
+                contents += &format!("best_time = {}, {:.2}\n", LEVEL_FILES[index], seconds);
+            }
+        }
+
+        if let Some(saved) = &self.level {
+            let (x, y, angle) = saved.player;
+            contents += &format!("\n# The level saved from the pause menu\nlevel = {}\n", LEVEL_FILES[saved.level]);
+            contents += &format!("difficulty = {}\n", saved.difficulty.name());
+            contents += &format!("player = {:.3}, {:.3}, {:.1}\n", x, y, angle.to_degrees());
+            contents += &format!("health = {}\nlives = {}\nhits = {}\n", saved.health, saved.lives, saved.hits_taken);
+            contents += &format!("elapsed = {:.3}\ncollected = {}\n", saved.elapsed, saved.pickups_collected);
+            for pickup in &saved.pickups {
+                contents += &format!("pickup = {:.3}, {:.3}\n", pickup.x, pickup.y);
+            }
+            for (position, facing) in &saved.cats {
+                contents += &format!("cat = {:.3}, {:.3}, {:.1}\n", position.x, position.y, facing.to_degrees());
+            }
+        }
+        contents
+    }
+
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level < self.unlocked
+    }
+
+    // Keeps the best time and unlocks the next level. A paused run of the
+    // same level is finished now, so it can no longer be loaded.
+    pub fn record_win(&mut self, level: usize, seconds: f32) {
+        if self.level.as_ref().is_some_and(|saved| saved.level == level) {
+            self.level = None;
+        }
+        let best = &mut self.best_times[level];
+        if best.is_none_or(|best| seconds < best) {
+            *best = Some(seconds);
+        }
+        self.unlocked = self.unlocked.max(level + 2).min(LEVEL_FILES.len());
+    }
+}
+
+// Upgrades the lines of an older save one version at a time. Versions that
+// were never written are refused instead of guessed at.
+fn migrate(path: &str, lines: Vec<ConfigLine>, version: u32) -> Result<Vec<ConfigLine>, Box<dyn Error>> {
+    if version < 1 {
+        return Err(format!("{}: unsupported save version {}", path, version).into());
+    }
+    if version > SAVE_VERSION {
+        return Err(format!("{}: saved by a newer version of the game (version {})", path, version).into());
+    }
+
+    let mut lines = lines;
+    for from in version..SAVE_VERSION {
+        lines = match from {
+            1 => lines.into_iter().map(name_level_files).collect(),
+            _ => return Err(format!("{}: no upgrade from save version {}", path, from).into()),
+        };
+    }
+    Ok(lines)
+}
+
+// Version 1 to 2: level numbers, counted from 1, become level files. Numbers
+// that name no level are left for loading to warn about.
+fn name_level_files(mut line: ConfigLine) -> ConfigLine {
+    let file = |number: &str| {
+        let number: usize = number.trim().parse().ok()?;
+        LEVEL_FILES.get(number.checked_sub(1)?).copied()
+    };
+    match line.key.as_str() {
+        "level" => {
+            if let Some(file) = file(&line.value) {
+                line.value = file.to_string();
+            }
+        }
+        "best_time" => {
+            if let Some((file, seconds)) = line.value.split_once(',').and_then(|(number, seconds)| Some((file(number)?, seconds))) {
+                line.value = format!("{}, {}", file, seconds.trim());
+            }
+        }
+        _ => {}
+    }
+    line
+}
+
+// Whether a saved point stands on a floor cell inside the maze
+fn is_open_floor(maze: &[Vec<char>], x: f32, y: f32) -> bool {
+    x.is_finite() && y.is_finite() && !is_wall_cell(maze, x.floor() as i32, y.floor() as i32)
+}
+
+// Levels are named by their level file
+fn level_index(file: &str) -> Option<usize> {
+    LEVEL_FILES.iter().position(|&level_file| level_file == file.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+    use crate::health::Health;
+    use crate::level::LevelFile;
+    use crate::player::Player;
+
+    fn load_str(contents: &str) -> Result<SaveFile, Box<dyn Error>> {
+        SaveFile::from_lines(SAVE_FILE, parse_config(SAVE_FILE, contents)?)
+    }
+
+    fn saved_level() -> SavedLevel {
+        SavedLevel {
+            level: 1,
+            difficulty: Difficulty::Hard,
+            player: (2.5, 3.25, 1.5),
+            health: 1,
+            lives: 2,
+            hits_taken: 4,
+            elapsed: 42.125,
+            pickups_collected: 1,
+            pickups: vec![na::Point3::new(4.5, 1.5, 0.0)],
+            cats: vec![(na::Point3::new(6.5, 2.5, 0.0), 0.5)],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_the_file_format() {
+        let mut save = SaveFile::default();
+        save.record_win(0, 31.5);
+        save.level = Some(saved_level());
+
+        let loaded = load_str(&save.to_contents()).unwrap();
+        assert_eq!(loaded.unlocked, 2);
+        assert_eq!(loaded.best_times, save.best_times);
+
+        let (saved, expected) = (loaded.level.unwrap(), saved_level());
+        assert_eq!(saved.level, expected.level);
+        assert_eq!(saved.difficulty, expected.difficulty);
+        assert!((saved.player.0 - 2.5).abs() < 1e-3 && (saved.player.1 - 3.25).abs() < 1e-3);
+        assert!((saved.player.2 - 1.5).abs() < 1e-3);
+        assert_eq!((saved.health, saved.lives, saved.hits_taken), (1, 2, 4));
+        assert!((saved.elapsed - 42.125).abs() < 1e-3);
+        assert_eq!(saved.pickups_collected, 1);
+        assert_eq!(saved.pickups, expected.pickups);
+        assert_eq!(saved.cats.len(), 1);
+        assert_eq!(saved.cats[0].0, expected.cats[0].0);
+        assert!((saved.cats[0].1 - 0.5).abs() < 1e-3);
+    }
+
+    // Level A as the playing scene loads it, without its images
+    fn level_a_world() -> World {
+        let file = LevelFile::read(LEVEL_FILES[0]).unwrap();
+        let mut player = Player::new(file.spawn.0, file.spawn.1, 1.0);
+        player.angle = file.spawn.2;
+        World::new(file.maze, player, file.cats, file.pickup_positions, file.objectives, Health::new(Difficulty::Normal.rules()))
+    }
+
+    #[test]
+    fn positions_off_the_floor_are_not_applied() {
+        let mut world = level_a_world();
+        let (spawn_x, spawn_y) = (world.player.x, world.player.y);
+        let cat_starts: Vec<na::Point3<f32>> = world.cats.iter().map(|cat| cat.position).collect();
+        let saved = SavedLevel {
+            level: 0,
+            player: (100.0, 100.0, 0.0),
+            lives: 99,
+            health: 0,
+            pickups: vec![na::Point3::new(-3.0, 1.5, 0.0), na::Point3::new(0.5, 0.5, 0.0), na::Point3::new(1.5, 1.5, 0.0)],
+            cats: world.cats.iter().map(|cat| (na::Point3::new(f32::NAN, cat.position.y, 0.0), 0.0)).collect(),
+            ..saved_level()
+        };
+        saved.apply(&mut world).unwrap();
+
+        assert_eq!((world.player.x, world.player.y), (spawn_x, spawn_y));
+        assert!(world.cats.iter().map(|cat| cat.position).eq(cat_starts));
+        assert_eq!(world.pickup_positions, [na::Point3::new(1.5, 1.5, 0.0)]);
+        let rules = Difficulty::Normal.rules();
+        assert_eq!((world.health.lives, world.health.health), (rules.lives, 1));
+
+        // The first tick used to index the maze with the saved position
+        assert_eq!(world.update(&Default::default(), &Default::default()), None);
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let err = load_str(&format!("version = {}\nunlocked = 2\n", SAVE_VERSION + 1)).err().unwrap();
+        assert!(err.to_string().contains("newer version"), "{}", err);
+    }
+
+    #[test]
+    fn refuses_versions_below_1() {
+        let err = load_str("version = 0\nunlocked = 2\n").err().unwrap();
+        assert_eq!(err.to_string(), "save.cfg: unsupported save version 0");
+    }
+
+    #[test]
+    fn version_1_level_numbers_become_level_files() {
+        let save = load_str("version = 1\nunlocked = 2\nbest_time = 2, 20.5\nlevel = 1\nhealth = 2\n").unwrap();
+        assert_eq!(save.unlocked, 2);
+        assert_eq!(save.best_times, [None, Some(20.5)]);
+        assert_eq!(save.level.as_ref().map(|level| (level.level, level.health)), Some((0, 2)));
+
+        // Saving again writes the current version, naming levels by file
+        let contents = save.to_contents();
+        assert!(contents.contains(&format!("version = {}\n", SAVE_VERSION)));
+        assert!(contents.contains(&format!("best_time = {}, 20.50\n", LEVEL_FILES[1])));
+        assert!(contents.contains(&format!("level = {}\n", LEVEL_FILES[0])));
+    }
+
+    #[test]
+    fn unversioned_files_load_as_version_1() {
+        let save = load_str("unlocked = 2\nbest_time = 1, 20.5\n").unwrap();
+        assert_eq!(save.unlocked, 2);
+        assert_eq!(save.best_times[0], Some(20.5));
+        assert!(save.level.is_none());
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        let contents = format!(
+            "version = 2\nunlocked = lots\nbest_time = {}, 20.5\nlives = 2\nbest_time = 9, 3\nlevel = {}\nhealth = 1\nmystery = 4\n",
+            LEVEL_FILES[0], LEVEL_FILES[1]
+        );
+        let save = load_str(&contents).unwrap();
+        assert_eq!(save.unlocked, 1);
+        assert_eq!(save.best_times[0], Some(20.5));
+        assert_eq!(save.level.map(|level| (level.level, level.health)), Some((1, 1)));
+    }
+
+    #[test]
+    fn read_only_saves_are_not_written() {
+        let save = SaveFile { read_only: true, ..SaveFile::default() };
+        let path = std::env::temp_dir().join("mundo3d_read_only_save.cfg");
+        assert!(save.save(path.to_str().unwrap()).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn winning_a_level_drops_its_paused_run() {
+        let mut save = SaveFile { level: Some(saved_level()), ..SaveFile::default() };
+        save.record_win(0, 30.0);
+        assert!(save.level.is_some(), "a different level was won");
+        save.record_win(1, 30.0);
+        assert!(save.level.is_none());
+    }
+}
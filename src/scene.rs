@@ -0,0 +1,133 @@
+use minifb::Window;
+use std::error::Error;
+
+use crate::assets::Assets;
+use crate::framebuffer::Framebuffer;
+use crate::input::{Input, MouseLook};
+use crate::player::Movement;
+use crate::save::SaveFile;
+use crate::scores::Leaderboard;
+use crate::settings::Settings;
+
+// State shared by every scene
+pub struct Context {
+    pub window: Window,
+    pub input: Input,
+    pub mouse_look: MouseLook,
+    pub settings: Settings,
+    pub save: SaveFile,
+    pub scores: Leaderboard,
+    pub movement: Movement,
+    pub assets: Assets,
+}
+
+// What the scene stack should do after a scene handled a frame
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),    // Put a new scene on top, keeping the current one below
+    Pop,                     // Go back to the scene below
+    Replace(Box<dyn Scene>), // Swap the current scene for a new one
+    Reset(Vec<Box<dyn Scene>>), // Drop every scene and start over from these, bottom first
+    Quit,
+}
+
+pub trait Scene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>>;
+
+    // Called once per frame after `handle_input` with the frame time in seconds
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<Transition, Box<dyn Error>> {
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, ctx: &Context);
+
+    // Called when the scene becomes the top of the stack, including when the
+    // scene above it is popped
+    fn on_enter(&mut self, _ctx: &mut Context) {}
+
+    // Overlays are drawn on top of the scene below them instead of replacing it
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    // Whether the mouse should stay captured for mouse look while this scene is on top
+    fn captures_mouse(&self) -> bool {
+        false
+    }
+}
+
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn new(root: Box<dyn Scene>, ctx: &mut Context) -> Self {
+        let mut stack = SceneStack { scenes: Vec::new() };
+        stack.apply(Transition::Push(root), ctx);
+        stack
+    }
+
+    pub fn push(&mut self, scene: Box<dyn Scene>, ctx: &mut Context) {
+        self.apply(Transition::Push(scene), ctx);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn captures_mouse(&self) -> bool {
+        self.scenes.last().is_some_and(|scene| scene.captures_mouse())
+    }
+
+    // Runs one frame of the top scene, returning false once the game should quit
+    pub fn frame(&mut self, ctx: &mut Context, dt: f32) -> Result<bool, Box<dyn Error>> {
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.handle_input(ctx)?,
+            None => return Ok(false),
+        };
+        if !matches!(transition, Transition::None) {
+            return Ok(self.apply(transition, ctx));
+        }
+
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(ctx, dt)?,
+            None => return Ok(false),
+        };
+        Ok(self.apply(transition, ctx))
+    }
+
+    // Draws the top scene and, for overlays, the scenes showing through below it
+    pub fn render(&self, framebuffer: &mut Framebuffer, ctx: &Context) {
+        let mut first_visible = self.scenes.len().saturating_sub(1);
+        while first_visible > 0 && self.scenes[first_visible].is_overlay() {
+            first_visible -= 1;
+        }
+        for scene in &self.scenes[first_visible..] {
+            scene.render(framebuffer, ctx);
+        }
+    }
+
+    fn apply(&mut self, transition: Transition, ctx: &mut Context) -> bool {
+        match transition {
+            Transition::None => return true,
+            Transition::Push(scene) => self.scenes.push(scene),
+            Transition::Pop => {
+                self.scenes.pop();
+            }
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            Transition::Reset(scenes) => self.scenes = scenes,
+            Transition::Quit => self.scenes.clear(),
+        }
+
+        match self.scenes.last_mut() {
+            Some(scene) => {
+                scene.on_enter(ctx);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::error::Error;
+
+use crate::controls::Action;
+use crate::framebuffer::Framebuffer;
+use crate::scene::{Context, Scene, Transition};
+use crate::ui::{Ui, UiEvent, Widget};
+
+const BUTTON_WIDTH: usize = 200;
+const BUTTON_HEIGHT: usize = 30;
+const BUTTON_SPACING: usize = 10;
+
+// Asks before closing the game so a stray Escape does not end it
+pub struct ConfirmQuitScene {
+    menu: Ui,
+}
+
+impl Default for ConfirmQuitScene {
+    fn default() -> Self {
+        let mut menu = Ui::new(Widget::panel("confirm_quit", vec![
+            Widget::label("title", "QUIT THE GAME?"),
+            Widget::button("no", "NO"),
+            Widget::button("yes", "YES"),
+        ]));
+        menu.root.layout_column(200, 240, BUTTON_WIDTH, BUTTON_HEIGHT, BUTTON_SPACING);
+        ConfirmQuitScene { menu }
+    }
+}
+
+impl Scene for ConfirmQuitScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        if ctx.input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Pop);
+        }
+
+        for event in self.menu.handle_input(&ctx.input.ui_input()) {
+            match event {
+                UiEvent::Activated(id) if id == "yes" => return Ok(Transition::Quit),
+                UiEvent::Activated(id) if id == "no" => return Ok(Transition::Pop),
+                _ => {}
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, _ctx: &Context) {
+        framebuffer.dim();
+        self.menu.draw(framebuffer);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,100 @@
+use std::error::Error;
+
+use minifb::Key;
+
+use crate::controls::{key_name, Action, Controls, CONTROLS_FILE};
+use crate::framebuffer::Framebuffer;
+use crate::scene::{Context, Scene, Transition};
+use crate::ui::{Ui, UiEvent, Widget};
+
+// Screen listing every action, where activating one waits for the next key press
+pub struct ControlsScene {
+    ui: Ui,
+    waiting_for: Option<Action>,
+    // The key pressed while waiting. It is bound once released, so holding it
+    // down does not also auto-repeat into the menu when it is a menu key.
+    pressed_key: Option<Key>,
+}
+
+const ROW_WIDTH: usize = 300;
+const ROW_HEIGHT: usize = 24;
+const ROW_SPACING: usize = 6;
+
+impl ControlsScene {
+    pub fn new(controls: &Controls) -> Self {
+        let mut rows = vec![Widget::label("title", "CONTROLS")];
+        rows.extend(Action::ALL.iter().map(|action| Widget::button(action.name(), "")));
+        rows.push(Widget::button("back", "BACK"));
+
+        let mut ui = Ui::new(Widget::panel("controls", rows));
+        ui.root.layout_column(150, 40, ROW_WIDTH, ROW_HEIGHT, ROW_SPACING);
+
+        let mut screen = ControlsScene { ui, waiting_for: None, pressed_key: None };
+        screen.refresh_labels(controls);
+        screen
+    }
+
+    fn refresh_labels(&mut self, controls: &Controls) {
+        for action in Action::ALL {
+            let keys = if self.waiting_for == Some(action) {
+                "PRESS A KEY".to_string()
+            } else {
+                controls.keys(action).iter().map(|key| key_name(*key)).collect::<Vec<_>>().join(", ")
+            };
+            if let Some(widget) = self.ui.widget_mut(action.name()) {
+                widget.label = format!("{}: {}", action.label(), keys);
+            }
+        }
+    }
+}
+
+impl Scene for ControlsScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        let input = &mut ctx.input;
+
+        if let Some(action) = self.waiting_for {
+            if self.pressed_key.is_none() {
+                // The pause key cancels, unless it is the one being rebound
+                if action != Action::Pause && input.is_action_pressed(Action::Pause) {
+                    self.waiting_for = None;
+                    self.refresh_labels(&input.controls);
+                    return Ok(Transition::None);
+                }
+                self.pressed_key = input.first_pressed_key();
+            }
+            match self.pressed_key {
+                Some(key) if input.is_released(key) => {
+                    input.controls.bind(action, key);
+                    if let Err(err) = input.controls.save(CONTROLS_FILE) {
+                        eprintln!("warning: could not write {}: {}", CONTROLS_FILE, err);
+                    }
+                    self.waiting_for = None;
+                    self.pressed_key = None;
+                    self.refresh_labels(&input.controls);
+                }
+                _ => {}
+            }
+            return Ok(Transition::None);
+        }
+
+        if input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Pop);
+        }
+
+        for event in self.ui.handle_input(&input.ui_input()) {
+            if let UiEvent::Activated(id) = event {
+                if id == "back" {
+                    return Ok(Transition::Pop);
+                }
+                self.waiting_for = Action::from_name(&id);
+                self.refresh_labels(&input.controls);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, _ctx: &Context) {
+        framebuffer.set_background_color(0xFF0C0B38);
+        self.ui.draw(framebuffer);
+    }
+}
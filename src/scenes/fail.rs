@@ -0,0 +1,66 @@
+use std::error::Error;
+
+use crate::controls::Action;
+use crate::framebuffer::Framebuffer;
+use crate::scene::{Context, Scene, Transition};
+use crate::ui::{Ui, UiEvent, Widget};
+
+use super::start::StartScene;
+
+const BUTTON_WIDTH: usize = 260;
+const BUTTON_HEIGHT: usize = 30;
+const BUTTON_SPACING: usize = 10;
+
+// Shown on top of the lost level, which stays below so it can be resumed
+// from its last checkpoint
+pub struct FailScene {
+    menu: Ui,
+}
+
+impl FailScene {
+    pub fn new(can_respawn: bool) -> Self {
+        let mut buttons = Vec::new();
+        if can_respawn {
+            buttons.push(Widget::button("respawn", "RESPAWN AT CHECKPOINT"));
+        }
+        buttons.push(Widget::button("quit", "QUIT TO MENU"));
+        let mut menu = Ui::new(Widget::panel("fail", buttons));
+        menu.root.layout_column(170, 480, BUTTON_WIDTH, BUTTON_HEIGHT, BUTTON_SPACING);
+        FailScene { menu }
+    }
+}
+
+impl Scene for FailScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        if ctx.input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Reset(vec![Box::new(StartScene::default())]));
+        }
+
+        // Enter picks the highlighted entry, like the menu's own key
+        if ctx.input.is_action_pressed(Action::Confirm) {
+            if let Some(id) = self.menu.focused_id() {
+                return Ok(choose(&id));
+            }
+        }
+        for event in self.menu.handle_input(&ctx.input.ui_input()) {
+            if let UiEvent::Activated(id) = event {
+                return Ok(choose(&id));
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, ctx: &Context) {
+        framebuffer.clear();
+        ctx.assets.fail_screen.draw(framebuffer, 0, 0);
+        self.menu.draw(framebuffer);
+    }
+}
+
+fn choose(id: &str) -> Transition {
+    match id {
+        "respawn" => Transition::Pop, // The level below restores its checkpoint
+        "quit" => Transition::Reset(vec![Box::new(StartScene::default())]),
+        _ => Transition::None,
+    }
+}
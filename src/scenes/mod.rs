@@ -0,0 +1,8 @@
+pub mod confirm_quit;
+pub mod controls;
+pub mod fail;
+pub mod pause;
+pub mod playing;
+pub mod settings;
+pub mod start;
+pub mod success;
@@ -0,0 +1,90 @@
+use std::error::Error;
+
+use crate::controls::Action;
+use crate::framebuffer::Framebuffer;
+use crate::save::{SavedLevel, SAVE_FILE};
+use crate::scene::{Context, Scene, Transition};
+use crate::ui::{Ui, UiEvent, Widget};
+
+use super::playing::{LevelSource, PlayingScene};
+use super::settings::SettingsScene;
+use super::start::StartScene;
+
+const BUTTON_WIDTH: usize = 200;
+const BUTTON_HEIGHT: usize = 30;
+const BUTTON_SPACING: usize = 10;
+
+// Overlay shown on top of a frozen level
+pub struct PauseScene {
+    source: LevelSource,
+    snapshot: Option<SavedLevel>, // The level as it is while paused, if it can be saved
+    menu: Ui,
+}
+
+impl PauseScene {
+    pub fn new(source: LevelSource, snapshot: Option<SavedLevel>) -> Self {
+        let mut buttons = vec![
+            Widget::label("title", "PAUSED"),
+            Widget::button("resume", "RESUME"),
+        ];
+        if snapshot.is_some() {
+            buttons.push(Widget::button("save", "SAVE GAME"));
+        }
+        buttons.extend([
+            Widget::button("restart", "RESTART LEVEL"),
+            Widget::button("settings", "SETTINGS"),
+            Widget::button("quit", "QUIT TO MENU"),
+        ]);
+        let mut menu = Ui::new(Widget::panel("pause", buttons));
+        menu.root.layout_column(200, 200, BUTTON_WIDTH, BUTTON_HEIGHT, BUTTON_SPACING);
+        PauseScene { source, snapshot, menu }
+    }
+}
+
+impl Scene for PauseScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        if ctx.input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Pop); // Pausing again resumes
+        }
+
+        for event in self.menu.handle_input(&ctx.input.ui_input()) {
+            if let UiEvent::Activated(id) = event {
+                return Ok(match id.as_str() {
+                    "resume" => Transition::Pop,
+                    "save" => {
+                        ctx.save.level = self.snapshot.clone();
+                        // A failed write should not end the game, only this save
+                        let label = match ctx.save.save(SAVE_FILE) {
+                            Ok(()) => "GAME SAVED",
+                            Err(err) => {
+                                eprintln!("warning: could not write {}: {}", SAVE_FILE, err);
+                                "SAVE FAILED"
+                            }
+                        };
+                        if let Some(button) = self.menu.widget_mut("save") {
+                            button.label = label.to_string();
+                        }
+                        Transition::None
+                    }
+                    "restart" => Transition::Reset(vec![
+                        Box::new(StartScene::default()),
+                        Box::new(PlayingScene::restart(self.source, ctx.settings.difficulty)?),
+                    ]),
+                    "settings" => Transition::Push(Box::new(SettingsScene::new(&ctx.settings))),
+                    "quit" => Transition::Reset(vec![Box::new(StartScene::default())]),
+                    _ => Transition::None,
+                });
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, _ctx: &Context) {
+        framebuffer.dim();
+        self.menu.draw(framebuffer);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
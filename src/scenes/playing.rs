@@ -0,0 +1,337 @@
+use std::error::Error;
+
+use crate::assets::Image;
+use crate::controls::Action;
+use crate::enemy::EnemyKind;
+use crate::font::{draw_text, text_width, LINE_ADVANCE};
+use crate::framebuffer::Framebuffer;
+use crate::game::{FixedTimestep, Outcome, World};
+use crate::generator::EndlessRun;
+use crate::health::{Difficulty, Health};
+use crate::input::player_command;
+use crate::level::Level;
+use crate::perception::AlertState;
+use crate::player::{Movement, Player, PlayerCommand};
+use crate::raycaster::cast_ray;
+use crate::save::{SavedLevel, SAVE_FILE};
+use crate::scene::{Context, Scene, Transition};
+use crate::scores::{format_time, score};
+
+use super::fail::FailScene;
+use super::pause::PauseScene;
+use super::success::{RunResults, SuccessScene};
+
+const CELL_SIZE: usize = 20;
+const MAX_MAP_SIZE: usize = 260; // Big mazes get smaller map cells so the map fits in the corner
+const FOV: f32 = std::f32::consts::PI / 3.0;
+const HURT_COLOR: u32 = 0xFFFF0000;
+
+// Where the level being played comes from
+#[derive(Clone, Copy)]
+pub enum LevelSource {
+    File(usize), // Index into LEVEL_FILES
+    Endless(EndlessRun),
+}
+
+pub struct PlayingScene {
+    source: LevelSource,
+    difficulty: Difficulty,
+    world: World,
+    name: String,
+    cat_img: Image,
+    cheshire_img: Option<Image>,
+    pickup_img: Option<Image>,
+    wall_texture: Image,
+    sky_color: u32,
+    timestep: FixedTimestep,
+    command: PlayerCommand,
+    failed: bool, // Set while the fail screen is on top, so coming back respawns
+}
+
+impl PlayingScene {
+    pub fn new(level: usize, difficulty: Difficulty) -> Result<Self, Box<dyn Error>> {
+        Ok(PlayingScene::with_level(Level::load(level)?, LevelSource::File(level), difficulty))
+    }
+
+    pub fn endless(run: EndlessRun, difficulty: Difficulty) -> Result<Self, Box<dyn Error>> {
+        Ok(PlayingScene::with_level(Level::generate(&run)?, LevelSource::Endless(run), difficulty))
+    }
+
+    // Starts the same level again from the beginning
+    pub fn restart(source: LevelSource, difficulty: Difficulty) -> Result<Self, Box<dyn Error>> {
+        match source {
+            LevelSource::File(level) => PlayingScene::new(level, difficulty),
+            LevelSource::Endless(run) => PlayingScene::endless(run, difficulty),
+        }
+    }
+
+    fn with_level(level_data: Level, source: LevelSource, difficulty: Difficulty) -> Self {
+        let mut player = Player::new(level_data.player_start_pos.0, level_data.player_start_pos.1, FOV);
+        player.angle = level_data.player_start_angle;
+        PlayingScene {
+            source,
+            difficulty,
+            world: World::new(level_data.maze, player, level_data.cats, level_data.pickup_positions, level_data.objectives, Health::new(difficulty.rules())),
+            name: level_data.name,
+            cat_img: level_data.cat_img,
+            cheshire_img: level_data.cheshire_img,
+            pickup_img: level_data.pickup_img,
+            wall_texture: level_data.wall_texture,
+            sky_color: level_data.sky_color,
+            timestep: FixedTimestep::default(),
+            command: PlayerCommand::default(),
+            failed: false,
+        }
+    }
+
+    // Continues a level saved from the pause menu
+    pub fn load(saved: &SavedLevel) -> Result<Self, Box<dyn Error>> {
+        let mut scene = PlayingScene::new(saved.level, saved.difficulty)?;
+        saved.apply(&mut scene.world)?;
+        Ok(scene)
+    }
+}
+
+impl Scene for PlayingScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        // The pause menu sits on top, so this scene stops updating until it is popped
+        if ctx.input.is_action_pressed(Action::Pause) {
+            // Generated mazes cannot be saved, only the level files
+            let snapshot = match self.source {
+                LevelSource::File(level) => Some(SavedLevel::capture(level, self.difficulty, &self.world)),
+                LevelSource::Endless(_) => None,
+            };
+            return Ok(Transition::Push(Box::new(PauseScene::new(self.source, snapshot))));
+        }
+
+        if ctx.input.is_action_pressed(Action::ToggleMouse) {
+            if ctx.mouse_look.is_captured() {
+                ctx.mouse_look.release(&mut ctx.window);
+            } else {
+                ctx.mouse_look.capture(&mut ctx.window);
+            }
+        } else if !ctx.mouse_look.is_captured() && ctx.input.is_mouse_clicked() {
+            ctx.mouse_look.capture(&mut ctx.window); // Clicking back into the game recaptures
+        }
+
+        self.world.turn_camera(ctx.mouse_look.turn(&ctx.input, &ctx.settings));
+        self.command = player_command(&ctx.input);
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<Transition, Box<dyn Error>> {
+        // Run as many fixed ticks as the elapsed time allows
+        for _ in 0..self.timestep.advance(dt) {
+            match self.world.update(&self.command, &ctx.movement) {
+                Some(Outcome::Success) => {
+                    let level = match self.source {
+                        LevelSource::File(level) => level,
+                        LevelSource::Endless(run) => {
+                            // On to a bigger maze, keeping whatever health is left
+                            let mut next = PlayingScene::endless(run.next(), self.difficulty)?;
+                            next.world.health = self.world.health.clone();
+                            return Ok(Transition::Replace(Box::new(next)));
+                        }
+                    };
+                    let world = &self.world;
+                    let results = RunResults {
+                        level,
+                        level_name: self.name.clone(),
+                        seconds: world.elapsed,
+                        previous_best: ctx.save.best_times[level],
+                        pickups_collected: world.pickups_collected,
+                        pickups_total: world.pickups_total,
+                        hits_taken: world.health.hits_taken,
+                        points: score(world.elapsed, world.pickups_collected, world.health.hits_taken),
+                    };
+                    ctx.save.record_win(level, world.elapsed);
+                    if let Err(err) = ctx.save.save(SAVE_FILE) {
+                        eprintln!("warning: could not write {}: {}", SAVE_FILE, err);
+                    }
+                    return Ok(Transition::Replace(Box::new(SuccessScene::new(results, &ctx.scores))));
+                }
+                Some(Outcome::Fail) => {
+                    self.failed = true;
+                    return Ok(Transition::Push(Box::new(FailScene::new(self.world.checkpoint.is_some()))));
+                }
+                None => {}
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, ctx: &Context) {
+        // Draw the player between the last two ticks for smooth motion
+        let player = self.world.interpolated_player(self.timestep.alpha());
+        let world = &self.world;
+        let cat_positions: Vec<na::Point3<f32>> = world.cats.iter().map(|cat| cat.interpolated_position(self.timestep.alpha())).collect();
+        let map_cell_size = CELL_SIZE.min(MAX_MAP_SIZE / world.maze[0].len()).min(MAX_MAP_SIZE / world.maze.len()).max(1);
+        framebuffer.render_fov_with_2d(&world.maze, &player, map_cell_size, &cat_positions, &self.wall_texture, self.sky_color);
+
+        // Render the cat images where they are between ticks
+        for (cat, cat_position) in world.cats.iter().zip(&cat_positions) {
+            let cat_img = match (cat.kind, &self.cheshire_img) {
+                (EnemyKind::Cheshire, Some(cheshire_img)) => cheshire_img,
+                _ => &self.cat_img,
+            };
+            render_cat_in_3d(
+                framebuffer,
+                &cat_img.pixels,
+                cat_img.width,
+                cat_img.height,
+                cat_position,
+                &player,
+                &world.maze,
+            );
+        }
+        if let Some(pickup_img) = &self.pickup_img {
+            for pickup_position in &world.pickup_positions {
+                render_cat_in_3d(framebuffer, &pickup_img.pixels, pickup_img.width, pickup_img.height, pickup_position, &player, &world.maze);
+            }
+        }
+        if world.health.is_flashing() {
+            framebuffer.tint(HURT_COLOR);
+        }
+        render_stamina_bar(framebuffer, &player, &ctx.movement);
+        render_health(framebuffer, &world.health);
+        draw_text(framebuffer, 10, 10, &self.name, 0xFFFFFFFF);
+        draw_text(framebuffer, 10, 10 + LINE_ADVANCE, &format!("TIME {}", format_time(world.elapsed)), 0xFFFFFFFF);
+        for (index, (line, done)) in world.objectives.status(world).iter().enumerate() {
+            let color = if *done { 0xFF00FF00 } else { 0xFFFFFFFF }; // Green once done
+            draw_text(framebuffer, 10, 10 + (index + 2) * LINE_ADVANCE, line, color);
+        }
+        render_alert(framebuffer, world);
+        if world.showing_checkpoint_message() {
+            let text = "CHECKPOINT";
+            draw_text(framebuffer, (framebuffer.width - text_width(text)) / 2, 10 + LINE_ADVANCE, text, 0xFF00FFFF);
+        }
+    }
+
+    fn on_enter(&mut self, ctx: &mut Context) {
+        // Back from the fail screen, which only pops when respawning
+        if self.failed {
+            self.failed = false;
+            self.world.restore_checkpoint();
+            self.timestep = FixedTimestep::default();
+        }
+        ctx.mouse_look.capture(&mut ctx.window);
+    }
+
+    fn captures_mouse(&self) -> bool {
+        true
+    }
+}
+
+const MAX_SCALE: f32 = 2.0; // Limit the maximum scale to avoid excessively large sprites
+
+fn project_to_2d(cat_position: &na::Point3<f32>, player: &Player, framebuffer_width: f32, framebuffer_height: f32) -> Option<(usize, usize, f32)> {
+    let dx = cat_position.x - player.x;
+    let dy = cat_position.y - player.y;
+
+    // Calculate the angle between the player and the cat
+    let angle_to_cat = dy.atan2(dx) - player.angle;
+    
+    // Ensure the angle is within the player's FOV
+    if angle_to_cat.abs() > player.fov / 2.0 {
+        return None; // Cat is outside the player's FOV, don't render it
+    }
+
+    // Calculate distance to the cat
+    let distance = (dx.powi(2) + dy.powi(2)).sqrt();
+
+    // Project the position to 2D screen space
+    let screen_x = ((angle_to_cat / player.fov) + 0.5) * framebuffer_width;
+    let scale = (400.0 / distance).min(MAX_SCALE); // Adjust the scale based on distance for perspective, and clamp
+
+    Some((screen_x as usize, (framebuffer_height / 2.0) as usize, scale))
+}
+
+fn render_cat_in_3d(
+    framebuffer: &mut Framebuffer, 
+    cat_img: &[u32], 
+    cat_width: usize, 
+    cat_height: usize, 
+    cat_position: &na::Point3<f32>, 
+    player: &Player, 
+    maze: &[Vec<char>]
+) {
+    let framebuffer_width = framebuffer.width as f32;
+    let framebuffer_height = framebuffer.height as f32;
+
+    if let Some((x, y, scale)) = project_to_2d(cat_position, player, framebuffer_width, framebuffer_height) {
+        let reduced_scale = scale * 0.5;
+
+        let scaled_width = (cat_width as f32 * reduced_scale).min(framebuffer_width);
+        let scaled_height = (cat_height as f32 * reduced_scale).min(framebuffer_height);
+
+        let angle_to_cat = (cat_position.y - player.y).atan2(cat_position.x - player.x);
+        let intersection = cast_ray(maze, player.x, player.y, angle_to_cat);
+
+        let distance_to_cat = ((cat_position.x - player.x).powi(2) + (cat_position.y - player.y).powi(2)).sqrt();
+        let cat_visible = intersection.distance >= distance_to_cat;
+
+        if !cat_visible {
+            return;
+        }
+
+        let start_x = x.saturating_sub(scaled_width as usize / 2);
+        let start_y = y.saturating_sub(scaled_height as usize / 2);
+
+        for dy in 0..scaled_height as usize {
+            for dx in 0..scaled_width as usize {
+                let pixel_index = (dy * cat_height / scaled_height as usize) * cat_width + (dx * cat_width / scaled_width as usize);
+                if pixel_index < cat_img.len() {
+                    let pixel = cat_img[pixel_index];
+                    if (pixel >> 24) & 0xFF != 0 { // Only draw if the pixel is not fully transparent
+                        let dest_x = start_x + dx;
+                        let dest_y = start_y + dy;
+                        if dest_x < framebuffer_width as usize && dest_y < framebuffer_height as usize {
+                            framebuffer.point(dest_x, dest_y, pixel);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Health and lives in the bottom-right corner, clear of the map
+fn render_health(framebuffer: &mut Framebuffer, health: &Health) {
+    let lines = [
+        format!("HEALTH {}/{}", health.health, health.rules.max_health),
+        format!("LIVES {}", health.lives),
+    ];
+    let top = framebuffer.height.saturating_sub(10 + lines.len() * LINE_ADVANCE);
+    for (index, line) in lines.iter().enumerate() {
+        let x = framebuffer.width.saturating_sub(text_width(line) + 10);
+        draw_text(framebuffer, x, top + index * LINE_ADVANCE, line, 0xFFFFFFFF);
+    }
+}
+
+// Warns the player when a cat is onto them
+fn render_alert(framebuffer: &mut Framebuffer, world: &World) {
+    let states: Vec<AlertState> = world.cats.iter().map(|cat| cat.perception.state).collect();
+    let (text, color) = if states.contains(&AlertState::Chase) {
+        ("SPOTTED!", 0xFFFF0000)
+    } else if states.iter().any(|&state| matches!(state, AlertState::Suspicious | AlertState::Search)) {
+        ("?", 0xFFFFFF00)
+    } else {
+        return;
+    };
+    draw_text(framebuffer, (framebuffer.width - text_width(text)) / 2, 10, text, color);
+}
+
+fn render_stamina_bar(framebuffer: &mut Framebuffer, player: &Player, movement: &Movement) {
+    let bar_width = 100;
+    let bar_height = 6;
+    let x_offset = 10;
+    let y_offset = framebuffer.height - bar_height - 10;
+    let filled = (bar_width as f32 * player.stamina / movement.max_stamina) as usize;
+
+    for y in y_offset..y_offset + bar_height {
+        for x in x_offset..x_offset + bar_width {
+            let color = if x - x_offset < filled { 0xFFFFD166 } else { 0xFF404040 }; // Yellow stamina over a dark track
+            framebuffer.point(x, y, color);
+        }
+    }
+}
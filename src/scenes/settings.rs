@@ -0,0 +1,78 @@
+use std::error::Error;
+
+use crate::controls::Action;
+use crate::framebuffer::Framebuffer;
+use crate::health::DIFFICULTIES;
+use crate::scene::{Context, Scene, Transition};
+use crate::settings::{Settings, MAX_MOUSE_SENSITIVITY, MIN_MOUSE_SENSITIVITY, SETTINGS_FILE};
+use crate::ui::{Ui, UiEvent, Widget};
+
+const ROW_WIDTH: usize = 300;
+const ROW_HEIGHT: usize = 24;
+const ROW_SPACING: usize = 6;
+
+pub struct SettingsScene {
+    ui: Ui,
+}
+
+impl SettingsScene {
+    pub fn new(settings: &Settings) -> Self {
+        let mut ui = Ui::new(Widget::panel("settings", vec![
+            Widget::label("title", "SETTINGS"),
+            Widget::slider(
+                "mouse_sensitivity",
+                "MOUSE SENSITIVITY",
+                settings.mouse_sensitivity,
+                MIN_MOUSE_SENSITIVITY,
+                MAX_MOUSE_SENSITIVITY,
+                1.0,
+            ),
+            Widget::toggle("invert_mouse", "INVERT MOUSE", settings.invert_mouse),
+            Widget::toggle("smooth_movement", "SMOOTH MOVEMENT", settings.smooth_movement),
+            Widget::list(
+                "difficulty",
+                "DIFFICULTY",
+                DIFFICULTIES.iter().map(|difficulty| difficulty.name().to_uppercase()).collect(),
+                DIFFICULTIES.iter().position(|&difficulty| difficulty == settings.difficulty).unwrap_or(0),
+            ),
+            Widget::button("back", "BACK"),
+        ]));
+        ui.root.layout_column(150, 200, ROW_WIDTH, ROW_HEIGHT, ROW_SPACING);
+        SettingsScene { ui }
+    }
+}
+
+impl Scene for SettingsScene {
+    // Applies and saves changes as they happen
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        if ctx.input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Pop);
+        }
+
+        let settings = &mut ctx.settings;
+        for event in self.ui.handle_input(&ctx.input.ui_input()) {
+            match event {
+                UiEvent::Activated(id) if id == "back" => return Ok(Transition::Pop),
+                UiEvent::Changed(_) => {
+                    settings.mouse_sensitivity = self.ui.slider_value("mouse_sensitivity").unwrap_or(settings.mouse_sensitivity);
+                    settings.invert_mouse = self.ui.toggle_value("invert_mouse").unwrap_or(settings.invert_mouse);
+                    settings.smooth_movement = self.ui.toggle_value("smooth_movement").unwrap_or(settings.smooth_movement);
+                    ctx.movement.inertia = settings.smooth_movement;
+                    if let Some(index) = self.ui.list_selection("difficulty") {
+                        settings.difficulty = DIFFICULTIES[index];
+                    }
+                    if let Err(err) = settings.save(SETTINGS_FILE) {
+                        eprintln!("warning: could not write {}: {}", SETTINGS_FILE, err);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, _ctx: &Context) {
+        framebuffer.set_background_color(0xFF0C0B38);
+        self.ui.draw(framebuffer);
+    }
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use crate::controls::Action;
+use crate::framebuffer::Framebuffer;
+use crate::generator::{random_seed, EndlessRun};
+use crate::save::SaveFile;
+use crate::scene::{Context, Scene, Transition};
+use crate::ui::{Ui, UiEvent, Widget};
+
+use super::confirm_quit::ConfirmQuitScene;
+use super::controls::ControlsScene;
+use super::playing::PlayingScene;
+use super::settings::SettingsScene;
+
+const BUTTON_WIDTH: usize = 100;
+const BUTTON_HEIGHT: usize = 30;
+const BUTTON_SPACING: usize = 20;
+const MENU_BOTTOM: usize = 580;
+
+pub struct StartScene {
+    menu: Ui,
+}
+
+impl Default for StartScene {
+    fn default() -> Self {
+        StartScene { menu: menu(&SaveFile::default()) }
+    }
+}
+
+// Locked levels stay listed so players can see what is ahead
+fn menu(save: &SaveFile) -> Ui {
+    let mut buttons = Vec::new();
+    for (index, name) in ["A", "B"].iter().enumerate() {
+        let label = if save.is_unlocked(index) { name.to_string() } else { format!("{} LOCKED", name) };
+        buttons.push(Widget::button(&format!("level_{}", index), &label));
+    }
+    buttons.push(Widget::button("endless", "ENDLESS"));
+    if save.level.is_some() {
+        buttons.push(Widget::button("load", "LOAD GAME"));
+    }
+    buttons.push(Widget::button("controls", "CONTROLS"));
+    buttons.push(Widget::button("settings", "SETTINGS"));
+
+    // Grow upwards from the bottom of the screen as buttons are added
+    let height = buttons.len() * (BUTTON_HEIGHT + BUTTON_SPACING) - BUTTON_SPACING;
+    let mut menu = Ui::new(Widget::panel("start", buttons));
+    menu.root.layout_column(100, MENU_BOTTOM - height, BUTTON_WIDTH, BUTTON_HEIGHT, BUTTON_SPACING);
+    menu
+}
+
+impl Scene for StartScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        if ctx.input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Push(Box::new(ConfirmQuitScene::default())));
+        }
+
+        for event in self.menu.handle_input(&ctx.input.ui_input()) {
+            if let UiEvent::Activated(id) = event {
+                return Ok(match id.as_str() {
+                    "level_0" if ctx.save.is_unlocked(0) => Transition::Push(Box::new(PlayingScene::new(0, ctx.settings.difficulty)?)), // Level 1
+                    "level_1" if ctx.save.is_unlocked(1) => Transition::Push(Box::new(PlayingScene::new(1, ctx.settings.difficulty)?)), // Level 2
+                    "endless" => Transition::Push(Box::new(PlayingScene::endless(EndlessRun::new(random_seed(), None), ctx.settings.difficulty)?)),
+                    "load" => match &ctx.save.level {
+                        Some(saved) => Transition::Push(Box::new(PlayingScene::load(saved)?)),
+                        None => Transition::None,
+                    },
+                    "controls" => Transition::Push(Box::new(ControlsScene::new(&ctx.input.controls))),
+                    "settings" => Transition::Push(Box::new(SettingsScene::new(&ctx.settings))),
+                    _ => Transition::None,
+                });
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, ctx: &Context) {
+        framebuffer.clear();
+        ctx.assets.start_screen.draw(framebuffer, 0, 0);
+        self.menu.draw(framebuffer);
+    }
+
+    // Levels unlock and saves appear while other scenes are on top
+    fn on_enter(&mut self, ctx: &mut Context) {
+        self.menu = menu(&ctx.save);
+    }
+}
@@ -0,0 +1,136 @@
+use std::error::Error;
+
+use minifb::Key;
+
+use crate::controls::Action;
+use crate::font::{draw_text, text_width, LINE_ADVANCE};
+use crate::framebuffer::Framebuffer;
+use crate::scene::{Context, Scene, Transition};
+use crate::scores::{clean_name, format_time, Leaderboard, Score, MAX_NAME_LENGTH, SCORES_FILE};
+use crate::ui::{Ui, UiEvent, UiInput, Widget};
+
+const BACKGROUND_COLOR: u32 = 0xFF0C0B38;
+const TEXT_COLOR: u32 = 0xFFFFFFFF;
+const HIGHLIGHT_COLOR: u32 = 0xFFFFD166;
+const LEFT: usize = 60;
+
+// How a won level went
+pub struct RunResults {
+    pub level: usize,
+    pub level_name: String,
+    pub seconds: f32,
+    pub previous_best: Option<f32>,
+    pub pickups_collected: usize,
+    pub pickups_total: usize,
+    pub hits_taken: u32,
+    pub points: u32,
+}
+
+// Shows the results of a won level and its high scores, asking for a name
+// first when the score makes it onto the table
+pub struct SuccessScene {
+    results: RunResults,
+    name_entry: Option<Ui>,
+    place: Option<usize>, // Where the new score landed on the table
+}
+
+impl SuccessScene {
+    pub fn new(results: RunResults, scores: &Leaderboard) -> Self {
+        let name_entry = scores.qualifies(results.level, results.points).then(|| {
+            let mut ui = Ui::new(Widget::panel("name_entry", vec![Widget::text_field("name", "NAME", "", MAX_NAME_LENGTH)]));
+            ui.root.layout_column(LEFT, 470, 300, 24, 6);
+            ui
+        });
+        SuccessScene { results, name_entry, place: None }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let results = &self.results;
+        let best = match results.previous_best {
+            Some(best) if best <= results.seconds => format!("BEST {}", format_time(best)),
+            _ => "NEW BEST TIME!".to_string(),
+        };
+        let mut lines = vec![
+            format!("{} COMPLETE", results.level_name.to_uppercase()),
+            String::new(),
+            format!("TIME {}   {}", format_time(results.seconds), best),
+        ];
+        if results.pickups_total > 0 {
+            lines.push(format!("BUNNIES {}/{}", results.pickups_collected, results.pickups_total));
+        }
+        lines.push(format!("HITS TAKEN {}", results.hits_taken));
+        lines.push(format!("SCORE {}", results.points));
+        lines
+    }
+}
+
+impl Scene for SuccessScene {
+    fn handle_input(&mut self, ctx: &mut Context) -> Result<Transition, Box<dyn Error>> {
+        if ctx.input.is_action_pressed(Action::Pause) {
+            return Ok(Transition::Pop); // Back to the start screen below, skipping the name
+        }
+
+        let Some(name_entry) = &mut self.name_entry else {
+            if ctx.input.is_action_pressed(Action::Confirm) || ctx.input.is_action_pressed(Action::Use) {
+                return Ok(Transition::Pop);
+            }
+            return Ok(Transition::None);
+        };
+
+        // Letters are typed into the name, so only Enter and Backspace act as keys
+        let input = UiInput {
+            activate: ctx.input.is_action_pressed(Action::Confirm),
+            backspace: ctx.input.is_repeated(Key::Backspace),
+            typed: ctx.input.ui_input().typed.into_iter().filter(|ch| ch.is_ascii_alphanumeric() || *ch == ' ').collect(),
+            ..UiInput::default()
+        };
+        for event in name_entry.handle_input(&input) {
+            if event == UiEvent::Activated("name".to_string()) {
+                let score = Score {
+                    name: clean_name(name_entry.text_value("name").unwrap_or("")),
+                    points: self.results.points,
+                    seconds: self.results.seconds,
+                };
+                self.place = ctx.scores.insert(self.results.level, score);
+                if let Err(err) = ctx.scores.save(SCORES_FILE) {
+                    eprintln!("warning: could not write {}: {}", SCORES_FILE, err);
+                }
+                self.name_entry = None;
+                break;
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, framebuffer: &mut Framebuffer, ctx: &Context) {
+        framebuffer.set_background_color(BACKGROUND_COLOR);
+
+        let mut y = 80;
+        for line in self.lines() {
+            draw_text(framebuffer, LEFT, y, &line, TEXT_COLOR);
+            y += 2 * LINE_ADVANCE;
+        }
+
+        y += 2 * LINE_ADVANCE;
+        draw_text(framebuffer, LEFT, y, "HIGH SCORES", TEXT_COLOR);
+        y += 2 * LINE_ADVANCE;
+        let scores = &ctx.scores.levels[self.results.level];
+        if scores.is_empty() {
+            draw_text(framebuffer, LEFT, y, "NO SCORES YET", TEXT_COLOR);
+        }
+        for (index, score) in scores.iter().enumerate() {
+            let color = if self.place == Some(index) { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+            let row = format!("{}. {:<width$} {:>6}  {}", index + 1, score.name, score.points, format_time(score.seconds), width = MAX_NAME_LENGTH);
+            draw_text(framebuffer, LEFT, y + index * 2 * LINE_ADVANCE, &row, color);
+        }
+
+        let footer = match &self.name_entry {
+            Some(name_entry) => {
+                name_entry.draw(framebuffer);
+                "NEW HIGH SCORE! TYPE YOUR NAME AND PRESS ENTER"
+            }
+            None => "PRESS ENTER TO CONTINUE",
+        };
+        draw_text(framebuffer, (framebuffer.width - text_width(footer)) / 2, framebuffer.height - 40, footer, TEXT_COLOR);
+    }
+}
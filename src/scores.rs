@@ -0,0 +1,163 @@
+use std::error::Error;
+use std::fs;
+
+use crate::config::{read_config, ConfigLine};
+use crate::level::LEVEL_FILES;
+
+pub const SCORES_FILE: &str = "scores.cfg";
+pub const MAX_NAME_LENGTH: usize = 10;
+const TABLE_SIZE: usize = 5; // Entries kept per level
+
+const BASE_SCORE: i32 = 1000;
+const POINTS_PER_PICKUP: i32 = 200;
+const POINTS_PER_HIT: i32 = 100; // Taken off for every hit from a cat
+const POINTS_PER_SECOND: i32 = 5; // Taken off for every second on the clock
+
+// Faster runs with more pickups and fewer hits score higher. Never below 0.
+pub fn score(seconds: f32, pickups_collected: usize, hits_taken: u32) -> u32 {
+    let points = BASE_SCORE + POINTS_PER_PICKUP * pickups_collected as i32
+        - POINTS_PER_HIT * hits_taken as i32
+        - POINTS_PER_SECOND * seconds as i32;
+    points.max(0) as u32
+}
+
+// Run times as minutes, seconds and tenths, e.g. 1:05.3
+pub fn format_time(seconds: f32) -> String {
+    let tenths = (seconds * 10.0) as u32;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+#[derive(Clone)]
+pub struct Score {
+    pub name: String,
+    pub points: u32,
+    pub seconds: f32,
+}
+
+// The best scores of each level, highest first
+pub struct Leaderboard {
+    pub levels: Vec<Vec<Score>>,
+    pub read_only: bool, // Set when the file could not be loaded, so the scores in it are not written over
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Leaderboard {
+            levels: vec![Vec::new(); LEVEL_FILES.len()],
+            read_only: false,
+        }
+    }
+}
+
+impl Leaderboard {
+    // Lines look like `score = level, name, points, seconds`, with levels numbered
+    // from 1. A bad line only loses its own score.
+    pub fn load(path: &str) -> Result<Leaderboard, Box<dyn Error>> {
+        match read_config(path)? {
+            Some(lines) => Ok(Leaderboard::from_lines(path, lines)),
+            None => Ok(Leaderboard::default()),
+        }
+    }
+
+    // Builds the tables from the lines of a file read from `path`
+    pub fn from_lines(path: &str, lines: Vec<ConfigLine>) -> Leaderboard {
+        let mut leaderboard = Leaderboard::default();
+        for line in lines {
+            if let Err(err) = leaderboard.read_line(path, &line) {
+                eprintln!("warning: {}; line ignored", err);
+            }
+        }
+
+        for scores in &mut leaderboard.levels {
+            sort(scores);
+            scores.truncate(TABLE_SIZE);
+        }
+        leaderboard
+    }
+
+    fn read_line(&mut self, path: &str, line: &ConfigLine) -> Result<(), String> {
+        let invalid = || format!("{}:{}: invalid value '{}' for {}", path, line.line_no, line.value, line.key);
+        if line.key != "score" {
+            return Err(format!("{}:{}: unknown entry '{}'", path, line.line_no, line.key));
+        }
+        let parts: Vec<&str> = line.value.split(',').map(str::trim).collect();
+        let [level, name, points, seconds] = parts[..] else {
+            return Err(invalid());
+        };
+        let level: usize = level.parse().map_err(|_| invalid())?;
+        let score = Score {
+            name: name.to_string(),
+            points: points.parse().map_err(|_| invalid())?,
+            seconds: seconds.parse().map_err(|_| invalid())?,
+        };
+        let scores = level.checked_sub(1).and_then(|index| self.levels.get_mut(index)).ok_or_else(invalid)?;
+        scores.push(score);
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        if self.read_only {
+            return Err(format!("{} could not be loaded, so it is left as it is", path).into());
+        }
+        let mut contents = String::from("# Mundo3D high scores: level, name, points, seconds\n");
+        for (index, scores) in self.levels.iter().enumerate() {
+            for score in scores {
+                contents += &format!("score = {}, {}, {}, {:.2}\n", index + 1, score.name, score.points, score.seconds);
+            }
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    // Whether a score would make it onto the level's table
+    pub fn qualifies(&self, level: usize, points: u32) -> bool {
+        let scores = &self.levels[level];
+        scores.len() < TABLE_SIZE || scores.last().is_some_and(|lowest| points > lowest.points)
+    }
+
+    // Adds a score and returns its place on the table, if it stayed on it
+    pub fn insert(&mut self, level: usize, score: Score) -> Option<usize> {
+        let scores = &mut self.levels[level];
+        // Later entries go below earlier ones with the same points
+        let place = scores.iter().position(|other| score.points > other.points).unwrap_or(scores.len());
+        scores.insert(place, score);
+        scores.truncate(TABLE_SIZE);
+        (place < TABLE_SIZE).then_some(place)
+    }
+}
+
+// Names are typed on the results screen. Commas would break the file format.
+pub fn clean_name(name: &str) -> String {
+    let name: String = name.chars().filter(|&ch| ch != ',').collect();
+    match name.trim() {
+        "" => "PLAYER".to_string(),
+        name => name.to_uppercase(),
+    }
+}
+
+fn sort(scores: &mut [Score]) {
+    scores.sort_by_key(|score| std::cmp::Reverse(score.points)); // Stable, so ties keep file order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+
+    #[test]
+    fn bad_lines_only_lose_their_own_score() {
+        let contents = "score = 1, ANA, 900, 20.5\nscore = 1, BEN, lots, 12\nscore = 9, CAT, 800, 30\nscore = 1, DAN, 1100, 15\n";
+        let leaderboard = Leaderboard::from_lines(SCORES_FILE, parse_config(SCORES_FILE, contents).unwrap());
+        let names: Vec<&str> = leaderboard.levels[0].iter().map(|score| score.name.as_str()).collect();
+        assert_eq!(names, ["DAN", "ANA"]);
+        assert!(leaderboard.levels[1].is_empty());
+    }
+
+    #[test]
+    fn read_only_tables_are_not_written() {
+        let leaderboard = Leaderboard { read_only: true, ..Leaderboard::default() };
+        let path = std::env::temp_dir().join("mundo3d_read_only_scores.cfg");
+        assert!(leaderboard.save(path.to_str().unwrap()).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fs;
+
+use crate::config::{read_config, ConfigLine};
+use crate::health::Difficulty;
+
+pub const SETTINGS_FILE: &str = "settings.cfg";
+
+pub const MOUSE_SENSITIVITY_SCALE: f32 = 0.001; // Radians turned per pixel at sensitivity 1
+pub const MIN_MOUSE_SENSITIVITY: f32 = 1.0;
+pub const MAX_MOUSE_SENSITIVITY: f32 = 20.0;
+
+pub struct Settings {
+    pub mouse_sensitivity: f32,
+    pub invert_mouse: bool,
+    pub smooth_movement: bool, // Accelerate and slide to a stop instead of changing speed at once
+    pub difficulty: Difficulty,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            mouse_sensitivity: 5.0,
+            invert_mouse: false,
+            smooth_movement: false,
+            difficulty: Difficulty::Normal,
+        }
+    }
+}
+
+impl Settings {
+    // Settings missing from the file keep their defaults. Problems are printed
+    // as warnings instead of stopping the game: a bad line keeps the default
+    // for its setting, an unreadable file keeps them all.
+    pub fn load(path: &str) -> Settings {
+        let mut settings = Settings::default();
+        let lines = match read_config(path) {
+            Ok(Some(lines)) => lines,
+            Ok(None) => return settings,
+            Err(err) => {
+                eprintln!("warning: {}; using the default settings", err);
+                return settings;
+            }
+        };
+
+        for line in lines {
+            if let Err(err) = settings.apply(path, &line) {
+                eprintln!("warning: {}; line ignored", err);
+            }
+        }
+        settings
+    }
+
+    // Sets the setting named by one line of the settings file
+    fn apply(&mut self, path: &str, line: &ConfigLine) -> Result<(), String> {
+        let invalid = || format!("{}:{}: invalid value '{}' for {}", path, line.line_no, line.value, line.key);
+        match line.key.as_str() {
+            "mouse_sensitivity" => {
+                let value: f32 = line.value.parse().map_err(|_| invalid())?;
+                self.mouse_sensitivity = value.clamp(MIN_MOUSE_SENSITIVITY, MAX_MOUSE_SENSITIVITY);
+            }
+            "invert_mouse" => self.invert_mouse = line.value.parse().map_err(|_| invalid())?,
+            "smooth_movement" => self.smooth_movement = line.value.parse().map_err(|_| invalid())?,
+            "difficulty" => self.difficulty = Difficulty::parse(&line.value).ok_or_else(invalid)?,
+            _ => return Err(format!("{}:{}: unknown setting '{}'", path, line.line_no, line.key)),
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let contents = format!(
+            "mouse_sensitivity = {}\ninvert_mouse = {}\nsmooth_movement = {}\ndifficulty = {}\n",
+            self.mouse_sensitivity,
+            self.invert_mouse,
+            self.smooth_movement,
+            self.difficulty.name()
+        );
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
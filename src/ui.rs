@@ -0,0 +1,425 @@
+use crate::button::Button;
+use crate::font::{draw_text, GLYPH_HEIGHT};
+use crate::framebuffer::Framebuffer;
+
+const LABEL_COLOR: u32 = 0xFFFFFFFF;
+const SLIDER_BAR_COLOR: u32 = 0xFF606C38;
+const SLIDER_BAR_HEIGHT: usize = 3;
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x as f32
+            && y >= self.y as f32
+            && x < (self.x + self.width) as f32
+            && y < (self.y + self.height) as f32
+    }
+}
+
+pub enum WidgetKind {
+    Panel(Vec<Widget>), // Groups children, laid out top to bottom
+    Label,
+    Button,
+    Slider { value: f32, min: f32, max: f32, step: f32 },
+    Toggle(bool),
+    TextField { text: String, max_len: usize },
+    List { items: Vec<String>, selected: usize },
+}
+
+pub struct Widget {
+    pub id: String,
+    pub label: String,
+    pub rect: Rect,
+    pub kind: WidgetKind,
+}
+
+// What the UI needs from the keyboard and mouse for one frame
+#[derive(Default)]
+pub struct UiInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub activate: bool,
+    pub backspace: bool,
+    pub typed: Vec<char>,
+    pub mouse_pos: Option<(f32, f32)>,
+    pub mouse_clicked: bool, // Only true on the frame the left button went down
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UiEvent {
+    Activated(String), // A button was pressed, a list confirmed or a text field submitted
+    Changed(String),   // A slider, toggle, list or text field changed its value
+}
+
+impl Widget {
+    fn new(id: &str, label: &str, kind: WidgetKind) -> Self {
+        Widget {
+            id: id.to_string(),
+            label: label.to_string(),
+            rect: Rect::default(),
+            kind,
+        }
+    }
+
+    pub fn panel(id: &str, children: Vec<Widget>) -> Self {
+        Widget::new(id, "", WidgetKind::Panel(children))
+    }
+
+    pub fn label(id: &str, text: &str) -> Self {
+        Widget::new(id, text, WidgetKind::Label)
+    }
+
+    pub fn button(id: &str, label: &str) -> Self {
+        Widget::new(id, label, WidgetKind::Button)
+    }
+
+    pub fn slider(id: &str, label: &str, value: f32, min: f32, max: f32, step: f32) -> Self {
+        let value = value.clamp(min, max);
+        Widget::new(id, label, WidgetKind::Slider { value, min, max, step })
+    }
+
+    pub fn toggle(id: &str, label: &str, on: bool) -> Self {
+        Widget::new(id, label, WidgetKind::Toggle(on))
+    }
+
+    pub fn text_field(id: &str, label: &str, text: &str, max_len: usize) -> Self {
+        Widget::new(id, label, WidgetKind::TextField { text: text.to_string(), max_len })
+    }
+
+    pub fn list(id: &str, label: &str, items: Vec<String>, selected: usize) -> Self {
+        let selected = selected.min(items.len().saturating_sub(1));
+        Widget::new(id, label, WidgetKind::List { items, selected })
+    }
+
+    pub fn is_focusable(&self) -> bool {
+        !matches!(self.kind, WidgetKind::Panel(_) | WidgetKind::Label)
+    }
+
+    // Stacks the widget and its children vertically starting at (x, y) and
+    // returns the height used
+    pub fn layout_column(&mut self, x: usize, y: usize, width: usize, row_height: usize, spacing: usize) -> usize {
+        match &mut self.kind {
+            WidgetKind::Panel(children) => {
+                let mut height = 0;
+                for child in children.iter_mut() {
+                    if height > 0 {
+                        height += spacing;
+                    }
+                    height += child.layout_column(x, y + height, width, row_height, spacing);
+                }
+                self.rect = Rect::new(x, y, width, height);
+                height
+            }
+            _ => {
+                self.rect = Rect::new(x, y, width, row_height);
+                row_height
+            }
+        }
+    }
+
+    pub fn display_text(&self, focused: bool) -> String {
+        match &self.kind {
+            WidgetKind::Panel(_) => String::new(),
+            WidgetKind::Label | WidgetKind::Button => self.label.clone(),
+            WidgetKind::Slider { value, step, .. } => {
+                if *step >= 1.0 {
+                    format!("{}: {:.0}", self.label, value)
+                } else {
+                    format!("{}: {:.2}", self.label, value)
+                }
+            }
+            WidgetKind::Toggle(on) => format!("{}: {}", self.label, if *on { "ON" } else { "OFF" }),
+            WidgetKind::TextField { text, .. } => {
+                let cursor = if focused { "_" } else { "" };
+                format!("{}: {}{}", self.label, text, cursor)
+            }
+            WidgetKind::List { items, selected } => {
+                let item = items.get(*selected).map(String::as_str).unwrap_or("");
+                format!("{}: < {} >", self.label, item)
+            }
+        }
+    }
+
+    fn draw(&self, framebuffer: &mut Framebuffer, focused_id: Option<&str>, hovered_id: Option<&str>) {
+        let focused = focused_id == Some(self.id.as_str());
+
+        match &self.kind {
+            WidgetKind::Panel(children) => {
+                for child in children {
+                    child.draw(framebuffer, focused_id, hovered_id);
+                }
+            }
+            WidgetKind::Label => {
+                let text_y = self.rect.y + self.rect.height.saturating_sub(GLYPH_HEIGHT) / 2;
+                draw_text(framebuffer, self.rect.x, text_y, &self.label, LABEL_COLOR);
+            }
+            _ => {
+                let mut button = self.as_button(focused);
+                button.is_hovered = hovered_id == Some(self.id.as_str());
+                button.draw(framebuffer);
+
+                // Sliders also show how far along their range they are
+                if let WidgetKind::Slider { value, min, max, .. } = &self.kind {
+                    let fraction = if max > min { (value - min) / (max - min) } else { 0.0 };
+                    let filled = (self.rect.width as f32 * fraction) as usize;
+                    let bar_y = self.rect.y + self.rect.height.saturating_sub(SLIDER_BAR_HEIGHT);
+                    for x in self.rect.x..self.rect.x + filled {
+                        for y in bar_y..bar_y + SLIDER_BAR_HEIGHT {
+                            framebuffer.point(x, y, SLIDER_BAR_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Focusable widgets are drawn and hit-tested as buttons
+    fn as_button(&self, focused: bool) -> Button {
+        let mut button = Button::new(self.rect.x, self.rect.y, self.rect.width, self.rect.height, &self.display_text(focused));
+        button.is_selected = focused;
+        button
+    }
+
+    // Returns the deepest focusable widget under the point, if any
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<&Widget> {
+        match &self.kind {
+            WidgetKind::Panel(children) => children.iter().find_map(|child| child.hit_test(x, y)),
+            _ if self.is_focusable() && self.rect.contains(x, y) => Some(self),
+            _ => None,
+        }
+    }
+
+    fn collect_focusable(&self, ids: &mut Vec<String>) {
+        if let WidgetKind::Panel(children) = &self.kind {
+            for child in children {
+                child.collect_focusable(ids);
+            }
+        } else if self.is_focusable() {
+            ids.push(self.id.clone());
+        }
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Widget> {
+        if self.id == id {
+            return Some(self);
+        }
+        match &self.kind {
+            WidgetKind::Panel(children) => children.iter().find_map(|child| child.find(id)),
+            _ => None,
+        }
+    }
+
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        if self.id == id {
+            return Some(self);
+        }
+        match &mut self.kind {
+            WidgetKind::Panel(children) => children.iter_mut().find_map(|child| child.find_mut(id)),
+            _ => None,
+        }
+    }
+
+    // Applies a left (-1) or right (+1) nudge, returning whether the value changed
+    fn adjust(&mut self, direction: i32) -> bool {
+        match &mut self.kind {
+            WidgetKind::Slider { value, min, max, step } => {
+                let new_value = (*value + *step * direction as f32).clamp(*min, *max);
+                let changed = new_value != *value;
+                *value = new_value;
+                changed
+            }
+            WidgetKind::Toggle(on) => {
+                *on = !*on;
+                true
+            }
+            WidgetKind::List { items, selected } if !items.is_empty() => {
+                let len = items.len() as i32;
+                *selected = ((*selected as i32 + direction).rem_euclid(len)) as usize;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Reacts to a click at horizontal position `x`, returning the resulting event
+    fn click(&mut self, x: f32) -> Option<UiEvent> {
+        match &mut self.kind {
+            WidgetKind::Button => Some(UiEvent::Activated(self.id.clone())),
+            WidgetKind::Slider { value, min, max, step } => {
+                // Jump to the clicked spot, snapped to the slider's step
+                let fraction = ((x - self.rect.x as f32) / self.rect.width.max(1) as f32).clamp(0.0, 1.0);
+                let raw = *min + (*max - *min) * fraction;
+                let snapped = if *step > 0.0 { *min + ((raw - *min) / *step).round() * *step } else { raw };
+                *value = snapped.clamp(*min, *max);
+                Some(UiEvent::Changed(self.id.clone()))
+            }
+            WidgetKind::Toggle(_) | WidgetKind::List { .. } => {
+                self.adjust(1);
+                Some(UiEvent::Changed(self.id.clone()))
+            }
+            _ => None, // Text fields only take the focus
+        }
+    }
+}
+
+pub struct Ui {
+    pub root: Widget,
+    focus: usize, // Index into the focusable widgets, in tree order
+    hovered: Option<String>,
+}
+
+impl Ui {
+    pub fn new(root: Widget) -> Self {
+        Ui {
+            root,
+            focus: 0,
+            hovered: None,
+        }
+    }
+
+    fn focusable_ids(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        self.root.collect_focusable(&mut ids);
+        ids
+    }
+
+    pub fn focused_id(&self) -> Option<String> {
+        let ids = self.focusable_ids();
+        ids.get(self.focus.min(ids.len().saturating_sub(1))).cloned()
+    }
+
+    pub fn set_focus(&mut self, id: &str) {
+        if let Some(index) = self.focusable_ids().iter().position(|focusable| focusable == id) {
+            self.focus = index;
+        }
+    }
+
+    pub fn widget(&self, id: &str) -> Option<&Widget> {
+        self.root.find(id)
+    }
+
+    pub fn widget_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        self.root.find_mut(id)
+    }
+
+    pub fn slider_value(&self, id: &str) -> Option<f32> {
+        match self.widget(id)?.kind {
+            WidgetKind::Slider { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn toggle_value(&self, id: &str) -> Option<bool> {
+        match self.widget(id)?.kind {
+            WidgetKind::Toggle(on) => Some(on),
+            _ => None,
+        }
+    }
+
+    pub fn text_value(&self, id: &str) -> Option<&str> {
+        match &self.widget(id)?.kind {
+            WidgetKind::TextField { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn list_selection(&self, id: &str) -> Option<usize> {
+        match &self.widget(id)?.kind {
+            WidgetKind::List { selected, .. } => Some(*selected),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer) {
+        let focused_id = self.focused_id();
+        self.root.draw(framebuffer, focused_id.as_deref(), self.hovered.as_deref());
+    }
+
+    pub fn handle_input(&mut self, input: &UiInput) -> Vec<UiEvent> {
+        let mut events = Vec::new();
+        let ids = self.focusable_ids();
+        if ids.is_empty() {
+            return events;
+        }
+
+        // Hovering only highlights; clicking moves the focus and activates
+        self.hovered = input
+            .mouse_pos
+            .and_then(|(x, y)| self.root.hit_test(x, y))
+            .map(|widget| widget.id.clone());
+
+        if input.mouse_clicked {
+            if let (Some(id), Some((x, _))) = (self.hovered.clone(), input.mouse_pos) {
+                self.set_focus(&id);
+                if let Some(event) = self.root.find_mut(&id).and_then(|widget| widget.click(x)) {
+                    events.push(event);
+                }
+            }
+        }
+
+        if input.down {
+            self.focus = (self.focus + 1) % ids.len();
+        }
+        if input.up {
+            self.focus = (self.focus + ids.len() - 1) % ids.len();
+        }
+
+        let focused_id = match self.focused_id() {
+            Some(id) => id,
+            None => return events,
+        };
+        let widget = match self.root.find_mut(&focused_id) {
+            Some(widget) => widget,
+            None => return events,
+        };
+
+        if input.left && widget.adjust(-1) {
+            events.push(UiEvent::Changed(focused_id.clone()));
+        }
+        if input.right && widget.adjust(1) {
+            events.push(UiEvent::Changed(focused_id.clone()));
+        }
+
+        if let WidgetKind::TextField { text, max_len } = &mut widget.kind {
+            let mut changed = false;
+            if input.backspace {
+                changed |= text.pop().is_some();
+            }
+            for &ch in &input.typed {
+                if text.chars().count() < *max_len && !ch.is_control() {
+                    text.push(ch);
+                    changed = true;
+                }
+            }
+            if changed {
+                events.push(UiEvent::Changed(focused_id.clone()));
+            }
+        }
+
+        if input.activate {
+            match widget.kind {
+                WidgetKind::Toggle(_) => {
+                    widget.adjust(1);
+                    events.push(UiEvent::Changed(focused_id));
+                }
+                WidgetKind::Slider { .. } => {}
+                _ => events.push(UiEvent::Activated(focused_id)),
+            }
+        }
+
+        events
+    }
+}
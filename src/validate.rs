@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::collision::{circle_hits_wall, is_wall_char, PLAYER_RADIUS};
+use crate::enemy::CAT_RADIUS;
+use crate::level::{LevelFile, LEVEL_FILES};
+use crate::objectives::Objective;
+
+// One problem found in a level file, pointing at the line and column to fix
+pub struct Diagnostic {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.path, self.line, self.column, self.message)
+    }
+}
+
+// Checks that a level can be played without falling out of the maze or
+// getting stuck. Returns every problem found, in file order.
+pub fn validate(level: &LevelFile) -> Vec<Diagnostic> {
+    let maze = &level.maze;
+    let mut diagnostics = Vec::new();
+    let mut report = |x: usize, y: usize, message: String| {
+        diagnostics.push(Diagnostic {
+            path: level.path.clone(),
+            line: level.grid_line + y,
+            column: x + 1,
+            message,
+        });
+    };
+
+    // Every row must be as wide as the first one
+    let width = maze[0].len();
+    for (y, row) in maze.iter().enumerate().skip(1) {
+        if row.len() != width {
+            report(row.len().min(width), y, format!("row is {} cells wide but the first row is {}", row.len(), width));
+        }
+    }
+
+    // Open cells on the edge would let the player walk off the maze
+    let last_row = maze.len() - 1;
+    for (y, row) in maze.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            let on_border = y == 0 || y == last_row || x == 0 || x == row.len() - 1;
+            if on_border && !is_wall_char(cell) {
+                report(x, y, format!("open border cell '{}'", cell));
+            }
+        }
+    }
+
+    let (spawn_x, spawn_y, _) = level.spawn;
+    let spawn_cell = match cell_at(maze, spawn_x, spawn_y) {
+        Some((x, y)) if !is_wall_char(maze[y][x]) => {
+            if circle_hits_wall(maze, spawn_x, spawn_y, PLAYER_RADIUS) {
+                report(x, y, format!("the spawn point ({}, {}) is too close to a wall", spawn_x, spawn_y));
+            }
+            Some((x, y))
+        }
+        Some((x, y)) => {
+            report(x, y, "the spawn point is inside a wall".to_string());
+            None
+        }
+        None => {
+            report(0, 0, format!("the spawn point ({}, {}) is outside the maze", spawn_x, spawn_y));
+            None
+        }
+    };
+
+    let goals = cells_marked(maze, 'g');
+    let needs_goal = level.objectives.win.contains(&Objective::ReachGoal);
+    if needs_goal && goals.is_empty() {
+        report(0, 0, "the maze has no goal 'g'".to_string());
+    }
+
+    let mut destinations = Vec::new();
+    let mut needs_pickups = false;
+    for objective in &level.objectives.win {
+        match *objective {
+            Objective::ReachCell(x, y) => match maze.get(y).and_then(|row| row.get(x)) {
+                Some(&cell) if is_wall_char(cell) => report(x, y, "the cell to reach is a wall".to_string()),
+                Some(_) => destinations.push((x, y)),
+                None => report(0, 0, format!("the cell to reach ({}, {}) is outside the maze", x, y)),
+            },
+            Objective::Collect(count) => {
+                needs_pickups = true;
+                if count.is_some_and(|count| count > level.pickup_positions.len()) {
+                    report(0, 0, format!("{} pickups must be collected but the level has {}", count.unwrap_or(0), level.pickup_positions.len()));
+                }
+            }
+            Objective::ReachGoal | Objective::Survive(_) => {}
+        }
+    }
+
+    // Cats walk straight between waypoints, so each leg of a patrol must be clear
+    for cat in &level.cats {
+        for leg in cat.waypoints.windows(2) {
+            if let Some((x, y)) = first_blocked_cell(maze, leg[0], leg[1]) {
+                report(x, y, format!("cat patrol from ({}, {}) to ({}, {}) runs into a wall", leg[0].x, leg[0].y, leg[1].x, leg[1].y));
+            }
+        }
+    }
+
+    let cat_points: Vec<na::Point3<f32>> = level.cats.iter().flat_map(|cat| cat.waypoints.iter().copied()).collect();
+    for (kind, positions) in [("cat", &cat_points), ("pickup", &level.pickup_positions)] {
+        for position in positions {
+            match cell_at(maze, position.x, position.y) {
+                Some((x, y)) if is_wall_char(maze[y][x]) => report(x, y, format!("{} is inside a wall", kind)),
+                Some(_) => {}
+                None => report(0, 0, format!("{} at ({}, {}) is outside the maze", kind, position.x, position.y)),
+            }
+        }
+    }
+
+    // Everything the player has to get to must be reachable from the spawn
+    if let Some(spawn_cell) = spawn_cell {
+        let reachable = reachable_cells(maze, spawn_cell);
+        if needs_goal && !goals.is_empty() && !goals.iter().any(|&(x, y)| reachable[y][x]) {
+            let (x, y) = goals[0];
+            report(x, y, "the goal cannot be reached from the spawn point".to_string());
+        }
+        for (x, y) in cells_marked(maze, 'k') {
+            if !reachable[y][x] {
+                report(x, y, "the checkpoint cannot be reached from the spawn point".to_string());
+            }
+        }
+        for &(x, y) in &destinations {
+            if !reachable[y][x] {
+                report(x, y, "the cell to reach cannot be reached from the spawn point".to_string());
+            }
+        }
+        if needs_pickups {
+            for position in &level.pickup_positions {
+                if let Some((x, y)) = cell_at(maze, position.x, position.y) {
+                    if !is_wall_char(maze[y][x]) && !reachable[y][x] {
+                        report(x, y, "pickup cannot be reached from the spawn point".to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    diagnostics
+}
+
+// The `validate` subcommand: checks the given level files, or the bundled ones,
+// and prints every problem. Returns false if any file has problems.
+pub fn run(paths: &[String]) -> bool {
+    let paths: Vec<String> = if paths.is_empty() {
+        LEVEL_FILES.iter().map(|path| path.to_string()).collect()
+    } else {
+        paths.to_vec()
+    };
+
+    let mut all_valid = true;
+    for path in &paths {
+        match LevelFile::parse(path) {
+            Ok(level) => {
+                let diagnostics = validate(&level);
+                if diagnostics.is_empty() {
+                    println!("{}: ok", path);
+                }
+                for diagnostic in &diagnostics {
+                    println!("{}", diagnostic);
+                    all_valid = false;
+                }
+            }
+            Err(err) => {
+                println!("{}", err);
+                all_valid = false;
+            }
+        }
+    }
+    all_valid
+}
+
+fn cells_marked(maze: &[Vec<char>], mark: char) -> Vec<(usize, usize)> {
+    maze.iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(move |(_, &cell)| cell == mark).map(move |(x, _)| (x, y)))
+        .collect()
+}
+
+// The maze cell holding a point, if the point is inside the maze
+fn cell_at(maze: &[Vec<char>], x: f32, y: f32) -> Option<(usize, usize)> {
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+    let (cell_x, cell_y) = (x as usize, y as usize);
+    maze.get(cell_y).and_then(|row| row.get(cell_x)).map(|_| (cell_x, cell_y))
+}
+
+// The first cell where a cat walking from `from` to `to` would touch a wall
+fn first_blocked_cell(maze: &[Vec<char>], from: na::Point3<f32>, to: na::Point3<f32>) -> Option<(usize, usize)> {
+    let steps = ((to - from).magnitude() / (CAT_RADIUS * 0.5)).ceil().max(1.0) as usize;
+    (0..=steps)
+        .map(|step| from + (to - from) * (step as f32 / steps as f32))
+        .find(|point| circle_hits_wall(maze, point.x, point.y, CAT_RADIUS))
+        .map(|point| (point.x.max(0.0) as usize, point.y.max(0.0) as usize))
+}
+
+// Flood fills the open cells connected to `start`
+fn reachable_cells(maze: &[Vec<char>], start: (usize, usize)) -> Vec<Vec<bool>> {
+    let mut reachable: Vec<Vec<bool>> = maze.iter().map(|row| vec![false; row.len()]).collect();
+    let mut queue = VecDeque::from([start]);
+    reachable[start.1][start.0] = true;
+
+    while let Some((x, y)) = queue.pop_front() {
+        let neighbours = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+        for (next_x, next_y) in neighbours {
+            let open = maze.get(next_y).and_then(|row| row.get(next_x)).is_some_and(|&cell| !is_wall_char(cell));
+            if open && !reachable[next_y][next_x] {
+                reachable[next_y][next_x] = true;
+                queue.push_back((next_x, next_y));
+            }
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A level with the required header keys, `extra` header lines, then the grid
+    fn level(extra: &str, grid: &[&str]) -> LevelFile {
+        let contents = format!("name = Test\nenemy_texture = cat.png\nwall_texture = wall.png\n{}---\n{}\n", extra, grid.join("\n"));
+        LevelFile::parse_contents("test.txt", &contents).unwrap()
+    }
+
+    // The only diagnostic for a level, as (line, column, message)
+    fn only_diagnostic(level: &LevelFile) -> (usize, usize, String) {
+        let diagnostics = validate(level);
+        let messages: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+        assert_eq!(diagnostics.len(), 1, "{:?}", messages);
+        let diagnostic = &diagnostics[0];
+        (diagnostic.line, diagnostic.column, diagnostic.message.clone())
+    }
+
+    #[test]
+    fn valid_level_has_no_diagnostics() {
+        let level = level("", &["+----+", "|p  g|", "+----+"]);
+        assert!(validate(&level).is_empty());
+    }
+
+    #[test]
+    fn missing_music_does_not_stop_a_level_loading() {
+        let level = level("music = sounds/missing.ogg\n", &["+----+", "|p  g|", "+----+"]);
+        assert!(level.checked().is_ok());
+    }
+
+    #[test]
+    fn reports_ragged_rows() {
+        // The grid starts on line 5, after three header lines and `---`
+        let level = level("", &["+----+", "|p g|", "+----+"]);
+        assert_eq!(only_diagnostic(&level), (6, 6, "row is 5 cells wide but the first row is 6".to_string()));
+    }
+
+    #[test]
+    fn reports_open_borders() {
+        let level = level("", &["+-- -+", "|p  g|", "+----+"]);
+        assert_eq!(only_diagnostic(&level), (5, 4, "open border cell ' '".to_string()));
+    }
+
+    #[test]
+    fn reports_a_missing_goal() {
+        let level = level("", &["+----+", "|p   |", "+----+"]);
+        assert_eq!(only_diagnostic(&level), (5, 1, "the maze has no goal 'g'".to_string()));
+    }
+
+    #[test]
+    fn reports_an_unreachable_goal() {
+        let level = level("", &["+----+", "|p |g|", "+----+"]);
+        assert_eq!(only_diagnostic(&level), (6, 5, "the goal cannot be reached from the spawn point".to_string()));
+    }
+
+    #[test]
+    fn reports_a_spawn_inside_a_wall() {
+        let level = level("spawn = 2.5, 0.5\n", &["+----+", "|   g|", "+----+"]);
+        assert_eq!(only_diagnostic(&level), (6, 3, "the spawn point is inside a wall".to_string()));
+    }
+
+    #[test]
+    fn diagnostics_print_as_path_line_and_column() {
+        let level = level("sky = 0xFF000000\n", &["+----+", "|  | |", "|p |g|", "+----+"]);
+        let diagnostics: Vec<String> = validate(&level).iter().map(|diagnostic| diagnostic.to_string()).collect();
+        assert_eq!(diagnostics, ["test.txt:8:5: the goal cannot be reached from the spawn point"]);
+    }
+}